@@ -0,0 +1,334 @@
+//! Async twin of [`ADXL345`](crate::ADXL345), over embedded-hal-async buses
+//! and interrupt pins
+
+use core::marker::PhantomData;
+
+use embedded_hal_async::{delay::DelayNs, digital::Wait};
+
+use crate::{
+    calibration::Calibration,
+    data_bus::{eh1, i2c::Address, AsyncDataBus},
+    error::Error,
+    fifo::{FIFO_DEPTH, FIFO_READ_GAP_US},
+    interrupt::{Events, Interrupt, NoPin, WaitError},
+    power::{Measuring, Standby},
+    register::{
+        power_ctl, FIFOMode, Readable, Writable, DATAX0, DATA_FORMAT, INT_ENABLE, POWER_CTL,
+    },
+    sample::{calibrated_g, calibrated_mg, g_to_mps2, RawSample, Sample, Scale},
+};
+
+/// Async driver for the device.
+///
+/// `MODE` works as for [`ADXL345`](crate::ADXL345). Interrupts are waited on
+/// through a pin implementing [`Wait`], so the task sleeps until it fires.
+pub struct ADXL345Async<BUS, MODE = Standby, INT = NoPin> {
+    pub(crate) bus: BUS,
+    /// Last value written to or read from `DATA_FORMAT`
+    pub(crate) data_format: u8,
+    /// Software calibration applied to scaled reads
+    calibration: Option<Calibration>,
+    int_pin: INT,
+    _mode: PhantomData<MODE>,
+}
+
+impl<SPI> ADXL345Async<eh1::SPIDeviceBus<SPI>>
+where
+    SPI: embedded_hal_async::spi::SpiDevice,
+{
+    pub fn from_spi_device(bus: SPI) -> Self {
+        Self::new(eh1::SPIDeviceBus::new(bus))
+    }
+}
+
+impl<I2C> ADXL345Async<eh1::I2CBus<I2C>>
+where
+    I2C: embedded_hal_async::i2c::I2c,
+{
+    pub fn from_i2c(bus: I2C, address: Address) -> Self {
+        Self::new(eh1::I2CBus::new(bus, address))
+    }
+}
+
+impl<BUS> ADXL345Async<BUS> {
+    fn new(bus: BUS) -> Self {
+        Self {
+            bus,
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<BUS, MODE, INT> ADXL345Async<BUS, MODE, INT> {
+    fn into_mode<M>(self) -> ADXL345Async<BUS, M, INT> {
+        ADXL345Async {
+            bus: self.bus,
+            data_format: self.data_format,
+            calibration: self.calibration,
+            int_pin: self.int_pin,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Scale matching the last value written to or read from `DATA_FORMAT`
+    pub fn scale(&self) -> Scale {
+        Scale::from_data_format(&DATA_FORMAT::fill(self.data_format))
+    }
+
+    /// Software calibration applied by the scaled reads, if any
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.calibration
+    }
+
+    /// Apply `calibration` in the scaled reads from now on, or stop
+    /// calibrating with `None`
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.calibration = calibration;
+    }
+}
+
+impl<BUS: AsyncDataBus, MODE, INT> ADXL345Async<BUS, MODE, INT> {
+    pub fn destroy(self) -> BUS::RawBus {
+        self.bus.destroy()
+    }
+
+    /// Read and clear the pending interrupts, together with the tap and
+    /// activity details in `ACT_TAP_STATUS`, which is read first
+    pub async fn poll_events(&mut self) -> Result<Events, Error<BUS::Error>> {
+        let status = self.act_tap_status().read().await?;
+        let source = self.int_source().read().await?;
+
+        Ok(Events::decode(source, status))
+    }
+
+    /// Write `ctl` changed by `f` to `POWER_CTL`, and return the value written
+    async fn update_power_ctl<F>(&mut self, ctl: u8, f: F) -> Result<u8, Error<BUS::Error>>
+    where
+        F: FnOnce(&mut power_ctl::Handle) -> &mut power_ctl::Handle,
+    {
+        let mut handle = POWER_CTL::fill(ctl);
+        f(&mut handle);
+        let ctl = POWER_CTL::into_raw(handle);
+
+        self.bus
+            .write::<POWER_CTL>(ctl)
+            .await
+            .map_err(Error::bus::<POWER_CTL>)?;
+        Ok(ctl)
+    }
+}
+
+impl<BUS: AsyncDataBus, INT> ADXL345Async<BUS, Standby, INT> {
+    /// Start measuring at the configured output data rate, waking the device
+    /// up if it was left asleep.
+    ///
+    /// `DATA_FORMAT` is read first, so that scaled reads and the interrupt
+    /// polarity follow the device even when it kept its settings across a
+    /// reset of the host.
+    ///
+    /// On failure the driver is handed back, still in standby.
+    #[allow(clippy::type_complexity)]
+    pub async fn into_measuring(
+        mut self,
+    ) -> Result<ADXL345Async<BUS, Measuring, INT>, (Self, Error<BUS::Error>)> {
+        match self.start_measuring().await {
+            Ok(()) => Ok(self.into_mode()),
+            Err(error) => Err((self, error)),
+        }
+    }
+
+    async fn start_measuring(&mut self) -> Result<(), Error<BUS::Error>> {
+        self.data_format().read().await?;
+        let ctl = self
+            .bus
+            .read::<POWER_CTL>()
+            .await
+            .map_err(Error::bus::<POWER_CTL>)?;
+        if POWER_CTL::fill(ctl).sleep() {
+            // Same sequence as the blocking driver: the sleep bit is only
+            // cleared in standby
+            let ctl = self.update_power_ctl(ctl, |w| w.set_measure(false)).await?;
+            let ctl = self.update_power_ctl(ctl, |w| w.set_sleep(false)).await?;
+            self.update_power_ctl(ctl, |w| w.set_measure(true)).await?;
+        } else {
+            self.update_power_ctl(ctl, |w| w.set_measure(true)).await?;
+        }
+        Ok(())
+    }
+
+    /// Set the FIFO mode and the number of samples that raise the watermark
+    /// interrupt, up to 31. The trigger pin is left untouched
+    pub async fn configure_fifo(
+        &mut self,
+        mode: FIFOMode,
+        watermark: u8,
+    ) -> Result<(), Error<BUS::Error>> {
+        if usize::from(watermark) >= FIFO_DEPTH {
+            return Err(Error::OutOfRange {
+                field: "watermark",
+                value: watermark.into(),
+            });
+        }
+
+        self.fifo_ctl()
+            .modify(|w| w.set_fifo_mode(mode).set_samples(watermark))
+            .await
+    }
+
+    /// Enable exactly the given interrupts, disabling the rest
+    pub async fn enable_interrupts(
+        &mut self,
+        interrupts: Interrupt,
+    ) -> Result<(), Error<BUS::Error>> {
+        self.bus
+            .write::<INT_ENABLE>(interrupts.bits())
+            .await
+            .map_err(Error::bus::<INT_ENABLE>)
+    }
+}
+
+impl<BUS: AsyncDataBus, INT> ADXL345Async<BUS, Measuring, INT> {
+    /// Stop measuring, keeping every other power setting.
+    ///
+    /// On failure the driver is handed back, still measuring.
+    #[allow(clippy::type_complexity)]
+    pub async fn into_standby(
+        mut self,
+    ) -> Result<ADXL345Async<BUS, Standby, INT>, (Self, Error<BUS::Error>)> {
+        let result = match self.bus.read::<POWER_CTL>().await {
+            Ok(ctl) => self.update_power_ctl(ctl, |w| w.set_measure(false)).await,
+            Err(e) => Err(Error::bus::<POWER_CTL>(e)),
+        };
+
+        match result {
+            Ok(_) => Ok(self.into_mode()),
+            Err(error) => Err((self, error)),
+        }
+    }
+
+    /// Read the three axes in a single multi-byte transaction, so that all of
+    /// them belong to the same measurement
+    pub async fn read_raw(&mut self) -> Result<RawSample, Error<BUS::Error>> {
+        let mut buffer = [0u8; 6];
+        self.bus
+            .read_all::<DATAX0>(&mut buffer)
+            .await
+            .map_err(Error::bus::<DATAX0>)?;
+        Ok(RawSample::from_le_bytes(buffer))
+    }
+
+    /// Read the three axes in mg, calibrated if a calibration is set
+    pub async fn read_mg(&mut self) -> Result<Sample<i32>, Error<BUS::Error>> {
+        let raw = self.read_raw().await?;
+        Ok(calibrated_mg(self.scale(), self.calibration, raw))
+    }
+
+    /// Read the three axes in g, calibrated if a calibration is set
+    pub async fn read_g(&mut self) -> Result<Sample<f32>, Error<BUS::Error>> {
+        let raw = self.read_raw().await?;
+        Ok(calibrated_g(self.scale(), self.calibration, raw))
+    }
+
+    /// Read the three axes in m/s², calibrated if a calibration is set
+    pub async fn read_mps2(&mut self) -> Result<Sample<f32>, Error<BUS::Error>> {
+        let g = self.read_g().await?;
+        Ok(g_to_mps2(g))
+    }
+
+    /// Move the samples stored in the FIFO into `samples`, oldest first, and
+    /// return how many were read.
+    ///
+    /// Only as many samples as `FIFO_STATUS` reported on entry are read, at
+    /// most `samples.len()`. `delay` spaces the reads as the datasheet
+    /// requires.
+    ///
+    /// Overruns are not checked, as reading `INT_SOURCE` would also clear the
+    /// latched tap, activity and free-fall events. Call
+    /// [`ADXL345Async::poll_events`] first and [`Events::check_overrun`] on
+    /// the result to find out whether samples were lost.
+    pub async fn read_fifo<D: DelayNs>(
+        &mut self,
+        samples: &mut [RawSample],
+        delay: &mut D,
+    ) -> Result<usize, Error<BUS::Error>> {
+        let entries = self.fifo_status().read().await?.entries();
+        let count = usize::from(entries).min(samples.len());
+
+        for sample in &mut samples[..count] {
+            *sample = self.read_raw().await?;
+            delay.delay_us(FIFO_READ_GAP_US).await;
+        }
+
+        Ok(count)
+    }
+}
+
+impl<BUS, MODE> ADXL345Async<BUS, MODE> {
+    /// Wait on `pin` for interrupts. Interrupts waited on must be routed to
+    /// that pin
+    pub fn with_int_pin<PIN: Wait>(self, pin: PIN) -> ADXL345Async<BUS, MODE, PIN> {
+        ADXL345Async {
+            bus: self.bus,
+            data_format: self.data_format,
+            calibration: self.calibration,
+            int_pin: pin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<BUS, MODE, PIN: Wait> ADXL345Async<BUS, MODE, PIN> {
+    /// Give the interrupt pin back
+    pub fn release_int_pin(self) -> (ADXL345Async<BUS, MODE>, PIN) {
+        (
+            ADXL345Async {
+                bus: self.bus,
+                data_format: self.data_format,
+                calibration: self.calibration,
+                int_pin: NoPin,
+                _mode: PhantomData,
+            },
+            self.int_pin,
+        )
+    }
+}
+
+impl<BUS: AsyncDataBus, MODE, PIN: Wait> ADXL345Async<BUS, MODE, PIN> {
+    /// Sleep until any enabled interrupt fires and return the decoded events
+    pub async fn wait_for_event(
+        &mut self,
+    ) -> Result<Events, WaitError<Error<BUS::Error>, PIN::Error>> {
+        loop {
+            self.wait_for_int_line().await?;
+
+            let events = self.poll_events().await.map_err(WaitError::Bus)?;
+            if !events.interrupts.is_empty() {
+                return Ok(events);
+            }
+        }
+    }
+
+    /// Wait until the interrupt pin is asserted, honouring the polarity set
+    /// in `DATA_FORMAT`
+    async fn wait_for_int_line(&mut self) -> Result<(), WaitError<Error<BUS::Error>, PIN::Error>> {
+        if DATA_FORMAT::fill(self.data_format).int_invert() {
+            self.int_pin.wait_for_low().await
+        } else {
+            self.int_pin.wait_for_high().await
+        }
+        .map_err(WaitError::Pin)
+    }
+}
+
+impl<BUS: AsyncDataBus, PIN: Wait> ADXL345Async<BUS, Measuring, PIN> {
+    /// Sleep until a new sample is ready. `DATA_READY` is expected to be the
+    /// only interrupt routed to the pin
+    pub async fn wait_for_data_ready(
+        &mut self,
+    ) -> Result<(), WaitError<Error<BUS::Error>, PIN::Error>> {
+        self.wait_for_int_line().await
+    }
+}
@@ -0,0 +1,380 @@
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::{
+    data_bus::DataBus, error::Error, power::Measuring, register::OFSX, sample::Sample, ADXL345,
+};
+
+/// Scale factor of the `OFSX`, `OFSY` and `OFSZ` registers, in mg/LSB
+const OFFSET_MG_PER_LSB: f32 = 15.6;
+
+/// Output periods to wait for the output to settle after clearing offsets
+const SETTLE_PERIODS: u32 = 4;
+
+/// Which axis of the device points up, against gravity, while it is held
+/// still
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    XUp,
+    XDown,
+    YUp,
+    YDown,
+    ZUp,
+    ZDown,
+}
+
+impl Orientation {
+    /// Every orientation, in declaration order
+    pub const ALL: [Orientation; 6] = [
+        Orientation::XUp,
+        Orientation::XDown,
+        Orientation::YUp,
+        Orientation::YDown,
+        Orientation::ZUp,
+        Orientation::ZDown,
+    ];
+
+    /// Acceleration the device should report in this orientation, in g
+    pub fn expected_g(self) -> Sample<f32> {
+        let mut g = Sample::default();
+        match self {
+            Orientation::XUp => g.x = 1.0,
+            Orientation::XDown => g.x = -1.0,
+            Orientation::YUp => g.y = 1.0,
+            Orientation::YDown => g.y = -1.0,
+            Orientation::ZUp => g.z = 1.0,
+            Orientation::ZDown => g.z = -1.0,
+        }
+        g
+    }
+}
+
+/// Per-axis gain and bias correction, applied in software on top of the
+/// offset registers, as `corrected = (measured - bias) * gain` in g
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    /// Unitless scale factor per axis, 1.0 leaving the reading as is
+    pub gain: Sample<f32>,
+    /// Zero-g reading per axis, in g, subtracted before scaling
+    pub bias: Sample<f32>,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            gain: Sample {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            bias: Sample::default(),
+        }
+    }
+}
+
+impl Calibration {
+    /// Size of the serialized form
+    pub const SIZE: usize = 24;
+
+    /// Correct an acceleration given in g
+    pub fn apply(&self, g: Sample<f32>) -> Sample<f32> {
+        Sample {
+            x: (g.x - self.bias.x) * self.gain.x,
+            y: (g.y - self.bias.y) * self.gain.y,
+            z: (g.z - self.bias.z) * self.gain.z,
+        }
+    }
+
+    /// Serialize as little-endian `f32`s, gains first, in axis order
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let values = [
+            self.gain.x,
+            self.gain.y,
+            self.gain.z,
+            self.bias.x,
+            self.bias.y,
+            self.bias.z,
+        ];
+
+        let mut bytes = [0; Self::SIZE];
+        for (chunk, value) in bytes.chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize what [`Calibration::to_bytes`] produced
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let mut values = [0f32; 6];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(4)) {
+            *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        Self {
+            gain: Sample {
+                x: values[0],
+                y: values[1],
+                z: values[2],
+            },
+            bias: Sample {
+                x: values[3],
+                y: values[4],
+                z: values[5],
+            },
+        }
+    }
+}
+
+/// Averaged readings taken in the six ±1 g orientations, from which a
+/// [`Calibration`] is solved
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SixPositionCalibration {
+    /// Reading of the vertical axis, in g, indexed as [`Orientation::ALL`]
+    readings: [Option<f32>; 6],
+}
+
+impl SixPositionCalibration {
+    /// Start with no orientation recorded.
+    ///
+    /// Orientations can be recorded in any order, and recording one again
+    /// replaces its reading. [`SixPositionCalibration::missing`] walks them
+    /// in the order of [`Orientation::ALL`], X up first and Z down last, for
+    /// prompting the user through them.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the reading of the vertical axis out of `g`, averaged with the
+    /// device held still in `orientation`
+    pub fn record(&mut self, orientation: Orientation, g: Sample<f32>) {
+        let reading = match orientation {
+            Orientation::XUp | Orientation::XDown => g.x,
+            Orientation::YUp | Orientation::YDown => g.y,
+            Orientation::ZUp | Orientation::ZDown => g.z,
+        };
+        self.readings[orientation as usize] = Some(reading);
+    }
+
+    /// First orientation not recorded yet
+    pub fn missing(&self) -> Option<Orientation> {
+        Orientation::ALL
+            .into_iter()
+            .find(|&orientation| self.readings[orientation as usize].is_none())
+    }
+
+    /// Solve gain and bias from the six orientations, or `None` if some are
+    /// missing or an axis reads lower facing up than facing down
+    pub fn solve(&self) -> Option<Calibration> {
+        let axis = |up: Orientation, down: Orientation| {
+            let up = self.readings[up as usize]?;
+            let down = self.readings[down as usize]?;
+            let sensitivity = (up - down) / 2.0;
+            (sensitivity > 0.0).then(|| (1.0 / sensitivity, (up + down) / 2.0))
+        };
+
+        let (gain_x, bias_x) = axis(Orientation::XUp, Orientation::XDown)?;
+        let (gain_y, bias_y) = axis(Orientation::YUp, Orientation::YDown)?;
+        let (gain_z, bias_z) = axis(Orientation::ZUp, Orientation::ZDown)?;
+
+        Some(Calibration {
+            gain: Sample {
+                x: gain_x,
+                y: gain_y,
+                z: gain_z,
+            },
+            bias: Sample {
+                x: bias_x,
+                y: bias_y,
+                z: bias_z,
+            },
+        })
+    }
+}
+
+/// Round to the nearest integer, away from zero on ties
+pub(crate) fn round(value: f32) -> i32 {
+    if value < 0.0 {
+        (value - 0.5) as i32
+    } else {
+        (value + 0.5) as i32
+    }
+}
+
+/// Offset register value cancelling the difference between `expected` and
+/// `measured`, both in g
+fn offset_lsb<E>(expected: f32, measured: f32) -> Result<i8, Error<E>> {
+    let lsb = (expected - measured) * 1000.0 / OFFSET_MG_PER_LSB;
+    let lsb = round(lsb);
+    i8::try_from(lsb).map_err(|_| Error::OutOfRange {
+        field: "offset",
+        value: lsb,
+    })
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Measuring, INT> {
+    /// Compute and write the offset registers, with the device held still in
+    /// `orientation`, and return them so they can be stored and restored
+    /// with [`Adxl345Config::offset`](crate::Adxl345Config::offset).
+    ///
+    /// Offsets are cleared first and `samples` readings, spaced by the output
+    /// data rate, are averaged. Offsets beyond the ±2 g the registers hold
+    /// are rejected.
+    pub fn calibrate_offsets<D: DelayUs<u32>>(
+        &mut self,
+        orientation: Orientation,
+        samples: u8,
+        delay: &mut D,
+    ) -> nb::Result<Sample<i8>, Error<BUS::Error>> {
+        if samples == 0 {
+            return Err(Error::OutOfRange {
+                field: "samples",
+                value: 0,
+            }
+            .into());
+        }
+
+        self.write_offsets(Sample::default())?;
+
+        let period_us = self.bw_rate().read()?.rate().period_us();
+        let measured = self.average_g(samples, period_us, period_us * SETTLE_PERIODS, delay)?;
+
+        let expected = orientation.expected_g();
+        let offsets = Sample {
+            x: offset_lsb(expected.x, measured.x)?,
+            y: offset_lsb(expected.y, measured.y)?,
+            z: offset_lsb(expected.z, measured.z)?,
+        };
+
+        self.write_offsets(offsets)?;
+
+        Ok(offsets)
+    }
+
+    /// Average `samples` uncalibrated readings, spaced by the output data
+    /// rate, with the device held still in `orientation`, and record them
+    /// into `positions`
+    pub fn record_position<D: DelayUs<u32>>(
+        &mut self,
+        positions: &mut SixPositionCalibration,
+        orientation: Orientation,
+        samples: u8,
+        delay: &mut D,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        if samples == 0 {
+            return Err(Error::OutOfRange {
+                field: "samples",
+                value: 0,
+            }
+            .into());
+        }
+
+        let period_us = self.bw_rate().read()?.rate().period_us();
+        let g = self.average_g(samples, period_us, 0, delay)?;
+
+        positions.record(orientation, g);
+        Ok(())
+    }
+}
+
+impl<BUS, MODE, INT> ADXL345<BUS, MODE, INT> {
+    /// Software calibration applied by the scaled reads, if any
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.calibration
+    }
+
+    /// Apply `calibration` in [`ADXL345::read_mg`], [`ADXL345::read_g`] and
+    /// [`ADXL345::read_mps2`] from now on, or stop calibrating with `None`
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.calibration = calibration;
+    }
+}
+
+impl<BUS: DataBus, MODE, INT> ADXL345<BUS, MODE, INT> {
+    /// Write `OFSX`, `OFSY` and `OFSZ` in one go.
+    ///
+    /// Unlike the rest of the configuration, offsets can also be written
+    /// while measuring, which is how the datasheet calibrates them.
+    pub fn write_offsets(&mut self, offsets: Sample<i8>) -> nb::Result<(), Error<BUS::Error>> {
+        self.bus
+            .write_all::<OFSX>(&[offsets.x as u8, offsets.y as u8, offsets.z as u8])
+            .map_err(|e| e.map(Error::bus::<OFSX>))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{offset_lsb, Calibration, Orientation, SixPositionCalibration};
+    use crate::{Error, Sample};
+
+    #[test]
+    fn offset_rounding() {
+        assert_eq!(offset_lsb::<()>(0.0, 0.0).unwrap(), 0);
+        assert_eq!(offset_lsb::<()>(1.0, 0.9).unwrap(), 6);
+        assert_eq!(offset_lsb::<()>(0.0, 0.1).unwrap(), -6);
+        assert_eq!(offset_lsb::<()>(0.0, -1.98).unwrap(), 127);
+
+        assert!(matches!(
+            offset_lsb::<()>(0.0, 2.5),
+            Err(Error::OutOfRange {
+                field: "offset",
+                value: -160
+            })
+        ));
+    }
+
+    #[test]
+    fn six_position() {
+        let mut positions = SixPositionCalibration::new();
+        let readings = [
+            (Orientation::XUp, 1.125),
+            (Orientation::XDown, -0.875),
+            (Orientation::YUp, 0.875),
+            (Orientation::YDown, -1.125),
+            (Orientation::ZUp, 1.25),
+        ];
+        for (orientation, reading) in readings {
+            let g = Sample {
+                x: reading,
+                y: reading,
+                z: reading,
+            };
+            positions.record(orientation, g);
+        }
+        assert_eq!(positions.missing(), Some(Orientation::ZDown));
+        assert_eq!(positions.solve(), None);
+
+        positions.record(
+            Orientation::ZDown,
+            Sample {
+                x: 0.0,
+                y: 0.0,
+                z: -0.75,
+            },
+        );
+        let calibration = positions.solve().unwrap();
+        assert_eq!(
+            calibration.bias,
+            Sample {
+                x: 0.125,
+                y: -0.125,
+                z: 0.25
+            }
+        );
+        assert_eq!(calibration.gain.z, 1.0);
+        assert_eq!(
+            calibration.apply(Sample {
+                x: 1.125,
+                y: -1.125,
+                z: -0.75
+            }),
+            Sample {
+                x: 1.0,
+                y: -1.0,
+                z: -1.0
+            }
+        );
+        assert_eq!(
+            Calibration::from_bytes(&calibration.to_bytes()),
+            calibration
+        );
+    }
+}
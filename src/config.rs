@@ -0,0 +1,327 @@
+use bitflags::bitflags;
+
+use crate::{
+    data_bus::DataBus,
+    error::Error,
+    interrupt::{IntPin, Interrupt},
+    power::Standby,
+    register::{
+        act_inact_ctl, FIFOMode, GRange, Latent, OutputDataRateHz, Readable, Register, Window,
+        Writable, ACT_INACT_CTL, BW_RATE, DATA_FORMAT, DUR, FIFO_CTL, FIFO_STATUS, INT_ENABLE,
+        INT_MAP, OFSX, OFSY, OFSZ, TAP_AXES, THRESH_ACT, THRESH_FF, THRESH_INACT, THRESH_TAP,
+        TIME_FF, TIME_INACT,
+    },
+    sample::Sample,
+    ADXL345,
+};
+
+bitflags! {
+    /// Set of axes, laid out as in the low bits of `TAP_AXES`,
+    /// `ACT_INACT_CTL` and `ACT_TAP_STATUS`
+    #[derive(Default)]
+    pub struct Axes: u8 {
+        const X = 0b100;
+        const Y = 0b010;
+        const Z = 0b001;
+    }
+}
+
+impl Axes {
+    pub(crate) fn from_enables(x: bool, y: bool, z: bool) -> Self {
+        let mut axes = Axes::empty();
+        axes.set(Axes::X, x);
+        axes.set(Axes::Y, y);
+        axes.set(Axes::Z, z);
+        axes
+    }
+}
+
+/// Convert `value` into a register holding `numerator / denominator` units per
+/// LSB, rounding to the nearest LSB
+pub(crate) fn to_lsb<E>(
+    value: u32,
+    numerator: u32,
+    denominator: u32,
+    name: &'static str,
+) -> Result<u8, Error<E>> {
+    let lsb = (u64::from(value) * u64::from(denominator) + u64::from(numerator / 2))
+        / u64::from(numerator);
+    u8::try_from(lsb).map_err(|_| Error::OutOfRange {
+        field: name,
+        value: i32::try_from(value).unwrap_or(i32::MAX),
+    })
+}
+
+/// Number of registers between `THRESH_TAP` and `FIFO_STATUS`, both included
+pub const REGISTER_SPAN: usize = (FIFO_STATUS::ADDRESS - THRESH_TAP::ADDRESS) as usize + 1;
+
+/// Device configuration, covering every writable register between
+/// `THRESH_TAP` and `FIFO_CTL` except `POWER_CTL`.
+///
+/// Values are kept in register units; the scale of each one is noted next to
+/// it. [`Default`] matches the power-on state of the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Adxl345Config {
+    /// Measurement range
+    pub range: GRange,
+    /// Keep 3.9 mg/LSB at every range instead of using 10-bit words
+    pub full_resolution: bool,
+    /// Left-justify the data registers
+    pub justify: bool,
+    /// Make the interrupt pins active low
+    pub int_invert: bool,
+    /// Output data rate
+    pub data_rate: OutputDataRateHz,
+    /// Reduced power operation, at the cost of more noise
+    pub low_power: bool,
+    /// FIFO operating mode
+    pub fifo_mode: FIFOMode,
+    /// Pin whose interrupt triggers the FIFO in trigger mode
+    pub fifo_trigger: IntPin,
+    /// FIFO watermark, or samples kept before the trigger in trigger mode
+    pub fifo_samples: u8,
+    /// Enabled interrupts
+    pub interrupts: Interrupt,
+    /// Interrupts routed to `INT2`, the rest go to `INT1`
+    pub int2: Interrupt,
+    /// Offset added to each axis, 15.6 mg/LSB
+    pub offset: Sample<i8>,
+    /// Tap threshold, 62.5 mg/LSB
+    pub tap_threshold: u8,
+    /// Maximum tap duration, 625 µs/LSB
+    pub tap_duration: u8,
+    /// Wait between a tap and the double tap window, 1.25 ms/LSB
+    pub tap_latency: u8,
+    /// Double tap window, 1.25 ms/LSB
+    pub tap_window: u8,
+    /// Axes taking part in tap detection
+    pub tap_axes: Axes,
+    /// Suppress double taps when acceleration stays above the threshold
+    /// between taps
+    pub tap_suppress: bool,
+    /// Activity threshold, 62.5 mg/LSB
+    pub activity_threshold: u8,
+    /// Axes taking part in activity detection
+    pub activity_axes: Axes,
+    /// AC-coupled activity detection
+    pub activity_ac: bool,
+    /// Inactivity threshold, 62.5 mg/LSB
+    pub inactivity_threshold: u8,
+    /// Inactivity time, 1 s/LSB
+    pub inactivity_time: u8,
+    /// Axes taking part in inactivity detection
+    pub inactivity_axes: Axes,
+    /// AC-coupled inactivity detection
+    pub inactivity_ac: bool,
+    /// Free-fall threshold, 62.5 mg/LSB
+    pub free_fall_threshold: u8,
+    /// Free-fall time, 5 ms/LSB
+    pub free_fall_time: u8,
+}
+
+impl Default for Adxl345Config {
+    fn default() -> Self {
+        Self {
+            range: GRange::Two,
+            full_resolution: false,
+            justify: false,
+            int_invert: false,
+            data_rate: OutputDataRateHz::_100,
+            low_power: false,
+            fifo_mode: FIFOMode::Bypass,
+            fifo_trigger: IntPin::Int1,
+            fifo_samples: 0,
+            interrupts: Interrupt::empty(),
+            int2: Interrupt::empty(),
+            offset: Sample::default(),
+            tap_threshold: 0,
+            tap_duration: 0,
+            tap_latency: 0,
+            tap_window: 0,
+            tap_axes: Axes::empty(),
+            tap_suppress: false,
+            activity_threshold: 0,
+            activity_axes: Axes::empty(),
+            activity_ac: false,
+            inactivity_threshold: 0,
+            inactivity_time: 0,
+            inactivity_axes: Axes::empty(),
+            inactivity_ac: false,
+            free_fall_threshold: 0,
+            free_fall_time: 0,
+        }
+    }
+}
+
+impl Adxl345Config {
+    /// Contents of the contiguous `THRESH_TAP`..`TIME_FF` registers
+    fn detection_registers(&self) -> [u8; 13] {
+        let mut act_inact_ctl = act_inact_ctl::Handle::default();
+        act_inact_ctl
+            .set_act_ac_dc(self.activity_ac)
+            .set_act_x_enable(self.activity_axes.contains(Axes::X))
+            .set_act_y_enable(self.activity_axes.contains(Axes::Y))
+            .set_act_z_enable(self.activity_axes.contains(Axes::Z))
+            .set_inact_ac_dc(self.inactivity_ac)
+            .set_inact_x_enable(self.inactivity_axes.contains(Axes::X))
+            .set_inact_y_enable(self.inactivity_axes.contains(Axes::Y))
+            .set_inact_z_enable(self.inactivity_axes.contains(Axes::Z));
+
+        [
+            self.tap_threshold,
+            self.offset.x as u8,
+            self.offset.y as u8,
+            self.offset.z as u8,
+            self.tap_duration,
+            self.tap_latency,
+            self.tap_window,
+            self.activity_threshold,
+            self.inactivity_threshold,
+            self.inactivity_time,
+            ACT_INACT_CTL::into_raw(act_inact_ctl),
+            self.free_fall_threshold,
+            self.free_fall_time,
+        ]
+    }
+
+    /// Decode the contents of the `THRESH_TAP`..`FIFO_STATUS` registers
+    pub fn from_registers(registers: &[u8; REGISTER_SPAN]) -> Self {
+        fn get<R: Register>(registers: &[u8; REGISTER_SPAN]) -> u8 {
+            registers[usize::from(R::ADDRESS - THRESH_TAP::ADDRESS)]
+        }
+
+        let act_inact_ctl = ACT_INACT_CTL::fill(get::<ACT_INACT_CTL>(registers));
+        let tap_axes = TAP_AXES::fill(get::<TAP_AXES>(registers));
+        let bw_rate = BW_RATE::fill(get::<BW_RATE>(registers));
+        let data_format = DATA_FORMAT::fill(get::<DATA_FORMAT>(registers));
+        let fifo_ctl = FIFO_CTL::fill(get::<FIFO_CTL>(registers));
+
+        Self {
+            range: data_format.range(),
+            full_resolution: data_format.full_res(),
+            justify: data_format.justify(),
+            int_invert: data_format.int_invert(),
+            data_rate: bw_rate.rate(),
+            low_power: bw_rate.low_power(),
+            fifo_mode: fifo_ctl.fifo_mode(),
+            fifo_trigger: if fifo_ctl.trigger() {
+                IntPin::Int2
+            } else {
+                IntPin::Int1
+            },
+            fifo_samples: fifo_ctl.samples(),
+            interrupts: Interrupt::from_bits_truncate(get::<INT_ENABLE>(registers)),
+            int2: Interrupt::from_bits_truncate(get::<INT_MAP>(registers)),
+            offset: Sample {
+                x: OFSX::fill(get::<OFSX>(registers)).value(),
+                y: OFSY::fill(get::<OFSY>(registers)).value(),
+                z: OFSZ::fill(get::<OFSZ>(registers)).value(),
+            },
+            tap_threshold: get::<THRESH_TAP>(registers),
+            tap_duration: get::<DUR>(registers),
+            tap_latency: get::<Latent>(registers),
+            tap_window: get::<Window>(registers),
+            tap_axes: Axes::from_enables(
+                tap_axes.tap_x_enable(),
+                tap_axes.tap_y_enable(),
+                tap_axes.tap_z_enable(),
+            ),
+            tap_suppress: tap_axes.suppress(),
+            activity_threshold: get::<THRESH_ACT>(registers),
+            activity_axes: Axes::from_enables(
+                act_inact_ctl.act_x_enable(),
+                act_inact_ctl.act_y_enable(),
+                act_inact_ctl.act_z_enable(),
+            ),
+            activity_ac: act_inact_ctl.act_ac_dc(),
+            inactivity_threshold: get::<THRESH_INACT>(registers),
+            inactivity_time: get::<TIME_INACT>(registers),
+            inactivity_axes: Axes::from_enables(
+                act_inact_ctl.inact_x_enable(),
+                act_inact_ctl.inact_y_enable(),
+                act_inact_ctl.inact_z_enable(),
+            ),
+            inactivity_ac: act_inact_ctl.inact_ac_dc(),
+            free_fall_threshold: get::<THRESH_FF>(registers),
+            free_fall_time: get::<TIME_FF>(registers),
+        }
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Standby, INT> {
+    /// Write the whole configuration to the device.
+    ///
+    /// Interrupts are disabled while the detection functions are set up and
+    /// enabled last, once they are routed to their pins. The 3-wire SPI
+    /// setting is left untouched.
+    pub fn apply_config(&mut self, config: &Adxl345Config) -> nb::Result<(), Error<BUS::Error>> {
+        self.bus
+            .write::<INT_ENABLE>(0)
+            .map_err(|e| e.map(Error::bus::<INT_ENABLE>))?;
+
+        self.bus
+            .write_all::<THRESH_TAP>(&config.detection_registers())
+            .map_err(|e| e.map(Error::bus::<THRESH_TAP>))?;
+        self.tap_axes().write(|w| {
+            w.set_suppress(config.tap_suppress)
+                .set_tap_x_enable(config.tap_axes.contains(Axes::X))
+                .set_tap_y_enable(config.tap_axes.contains(Axes::Y))
+                .set_tap_z_enable(config.tap_axes.contains(Axes::Z))
+        })?;
+
+        self.bw_rate()
+            .write(|w| w.set_low_power(config.low_power).set_rate(config.data_rate))?;
+
+        let spi = DATA_FORMAT::fill(self.data_format).spi();
+        self.data_format().write(|w| {
+            w.set_spi(spi)
+                .set_int_invert(config.int_invert)
+                .set_full_res(config.full_resolution)
+                .set_justify(config.justify)
+                .set_range(config.range)
+        })?;
+
+        self.fifo_ctl().write(|w| {
+            w.set_fifo_mode(config.fifo_mode)
+                .set_trigger(config.fifo_trigger == IntPin::Int2)
+                .set_samples(config.fifo_samples)
+        })?;
+
+        self.bus
+            .write::<INT_MAP>(config.int2.bits())
+            .map_err(|e| e.map(Error::bus::<INT_MAP>))?;
+        self.bus
+            .write::<INT_ENABLE>(config.interrupts.bits())
+            .map_err(|e| e.map(Error::bus::<INT_ENABLE>))?;
+
+        Ok(())
+    }
+}
+
+impl<BUS: DataBus, MODE, INT> ADXL345<BUS, MODE, INT> {
+    /// Read the whole configuration back from the device.
+    ///
+    /// `THRESH_TAP` to `INT_MAP` are read in a single multi-byte transaction,
+    /// followed by `DATA_FORMAT` and `FIFO_CTL`. `INT_SOURCE` and the data
+    /// registers are skipped, as reading them clears pending interrupts and
+    /// pops the FIFO.
+    pub fn read_config(&mut self) -> nb::Result<Adxl345Config, Error<BUS::Error>> {
+        let mut registers = [0u8; REGISTER_SPAN];
+        let offset = |address: u8| usize::from(address - THRESH_TAP::ADDRESS);
+
+        self.bus
+            .read_all::<THRESH_TAP>(&mut registers[..=offset(INT_MAP::ADDRESS)])
+            .map_err(|e| e.map(Error::bus::<THRESH_TAP>))?;
+        registers[offset(DATA_FORMAT::ADDRESS)] = self
+            .bus
+            .read::<DATA_FORMAT>()
+            .map_err(|e| e.map(Error::bus::<DATA_FORMAT>))?;
+        registers[offset(FIFO_CTL::ADDRESS)] = self
+            .bus
+            .read::<FIFO_CTL>()
+            .map_err(|e| e.map(Error::bus::<FIFO_CTL>))?;
+        self.data_format = registers[offset(DATA_FORMAT::ADDRESS)];
+
+        Ok(Adxl345Config::from_registers(&registers))
+    }
+}
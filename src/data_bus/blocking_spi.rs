@@ -0,0 +1,181 @@
+use core::fmt;
+
+use embedded_hal::{
+    blocking::spi::{Transfer, Write},
+    digital::v2::OutputPin,
+};
+
+use crate::register::Register;
+
+use super::{DataBus, MessageFlags};
+
+/// Largest burst, in bytes after the command byte, that fits the transfer
+/// buffer. This covers every register from `THRESH_TAP` to `FIFO_STATUS`
+const MAX_BURST: usize = 29;
+
+/// 4-wire SPI bus over the blocking `Transfer` and `Write` traits, moving a
+/// whole register burst in a single call
+pub struct BlockingSPIBus<SPI, CS> {
+    bus: SPI,
+    cs: CS,
+}
+
+impl<SPI, CS> BlockingSPIBus<SPI, CS>
+where
+    SPI: Transfer<u8> + Write<u8>,
+    CS: OutputPin,
+{
+    pub fn new(bus: SPI, cs: CS) -> Self {
+        Self { bus, cs }
+    }
+
+    /// Run `f` with chip select asserted. Chip select is released even if
+    /// `f` fails, and the first error is reported
+    fn transaction<T, F>(&mut self, f: F) -> Result<T, BlockingSpiError<SPI, CS>>
+    where
+        F: FnOnce(&mut SPI) -> Result<T, BlockingSpiError<SPI, CS>>,
+    {
+        self.cs.set_low().map_err(BlockingSpiError::ChipSelect)?;
+        let result = f(&mut self.bus);
+        let released = self.cs.set_high().map_err(BlockingSpiError::ChipSelect);
+
+        result.and_then(|value| released.map(|_| value))
+    }
+}
+
+impl<SPI, CS> DataBus for BlockingSPIBus<SPI, CS>
+where
+    SPI: Transfer<u8> + Write<u8>,
+    CS: OutputPin,
+{
+    type Error = BlockingSpiError<SPI, CS>;
+    type RawBus = (SPI, CS);
+
+    fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        if buffer.len() > MAX_BURST {
+            return Err(BlockingSpiError::Capacity.into());
+        }
+
+        let mut words = [0u8; MAX_BURST + 1];
+        let words = &mut words[..=buffer.len()];
+        words[0] = MessageFlags::READ
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+
+        self.transaction(|spi| {
+            let received = spi.transfer(words).map_err(BlockingSpiError::Transfer)?;
+            buffer.copy_from_slice(&received[1..]);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn write_all<R: Register>(&mut self, buffer: &[u8]) -> nb::Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        if buffer.len() > MAX_BURST {
+            return Err(BlockingSpiError::Capacity.into());
+        }
+
+        let mut words = [0u8; MAX_BURST + 1];
+        let words = &mut words[..=buffer.len()];
+        words[0] = MessageFlags::WRITE
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        words[1..].copy_from_slice(buffer);
+
+        self.transaction(|spi| spi.write(words).map_err(BlockingSpiError::Write))?;
+        Ok(())
+    }
+
+    fn read<R: Register>(&mut self) -> nb::Result<u8, Self::Error> {
+        let mut words = [
+            MessageFlags::READ
+                .union(MessageFlags::SINGLE)
+                .register::<R>(),
+            0,
+        ];
+
+        let data = self.transaction(|spi| {
+            spi.transfer(&mut words)
+                .map(|received| received[1])
+                .map_err(BlockingSpiError::Transfer)
+        })?;
+        Ok(data)
+    }
+
+    fn write<R: Register>(&mut self, data: u8) -> nb::Result<(), Self::Error> {
+        let words = [
+            MessageFlags::WRITE
+                .union(MessageFlags::SINGLE)
+                .register::<R>(),
+            data,
+        ];
+
+        self.transaction(|spi| spi.write(&words).map_err(BlockingSpiError::Write))?;
+        Ok(())
+    }
+
+    fn destroy(self) -> Self::RawBus {
+        (self.bus, self.cs)
+    }
+}
+
+pub enum BlockingSpiError<SPI, CS>
+where
+    SPI: Transfer<u8> + Write<u8>,
+    CS: OutputPin,
+{
+    /// SPI error occured during a transfer
+    Transfer(<SPI as Transfer<u8>>::Error),
+
+    /// SPI error occured during a write
+    Write(<SPI as Write<u8>>::Error),
+
+    /// Burst longer than the transfer buffer
+    Capacity,
+
+    /// Error occured while changing chip select signal
+    ChipSelect(<CS as OutputPin>::Error),
+}
+
+impl<SPI, CS> fmt::Debug for BlockingSpiError<SPI, CS>
+where
+    SPI: Transfer<u8> + Write<u8>,
+    CS: OutputPin,
+    <SPI as Transfer<u8>>::Error: fmt::Debug,
+    <SPI as Write<u8>>::Error: fmt::Debug,
+    <CS as OutputPin>::Error: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockingSpiError::Transfer(error) => write!(f, "Transfer({error:?})"),
+            BlockingSpiError::Write(error) => write!(f, "Write({error:?})"),
+            BlockingSpiError::Capacity => write!(f, "Capacity"),
+            BlockingSpiError::ChipSelect(error) => write!(f, "ChipSelect({error:?})"),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<SPI, CS> defmt::Format for BlockingSpiError<SPI, CS>
+where
+    SPI: Transfer<u8> + Write<u8>,
+    CS: OutputPin,
+    <SPI as Transfer<u8>>::Error: defmt::Format,
+    <SPI as Write<u8>>::Error: defmt::Format,
+    <CS as OutputPin>::Error: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            BlockingSpiError::Transfer(error) => defmt::write!(f, "Transfer({})", error),
+            BlockingSpiError::Write(error) => defmt::write!(f, "Write({})", error),
+            BlockingSpiError::Capacity => defmt::write!(f, "Capacity"),
+            BlockingSpiError::ChipSelect(error) => defmt::write!(f, "ChipSelect({})", error),
+        }
+    }
+}
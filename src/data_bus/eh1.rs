@@ -0,0 +1,233 @@
+//! Buses over the embedded-hal 1.0 `SpiDevice` and `I2c` traits, and over
+//! their embedded-hal-async counterparts
+
+use embedded_hal_1::{
+    i2c::{self, I2c},
+    spi::{Operation, SpiDevice},
+};
+
+use crate::register::Register;
+
+use super::{i2c::Address, DataBus, MessageFlags};
+
+/// 4-wire SPI bus over a [`SpiDevice`], which manages chip select itself.
+/// With the `async` feature it also drives async SPI devices
+pub struct SPIDeviceBus<SPI> {
+    bus: SPI,
+}
+
+impl<SPI> SPIDeviceBus<SPI> {
+    pub fn new(bus: SPI) -> Self {
+        Self { bus }
+    }
+}
+
+impl<SPI: SpiDevice> DataBus for SPIDeviceBus<SPI> {
+    type Error = SPI::Error;
+    type RawBus = SPI;
+
+    fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let command = MessageFlags::READ
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        self.bus
+            .transaction(&mut [Operation::Write(&[command]), Operation::Read(buffer)])?;
+        Ok(())
+    }
+
+    fn write_all<R: Register>(&mut self, buffer: &[u8]) -> nb::Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let command = MessageFlags::WRITE
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        self.bus
+            .transaction(&mut [Operation::Write(&[command]), Operation::Write(buffer)])?;
+        Ok(())
+    }
+
+    fn read<R: Register>(&mut self) -> nb::Result<u8, Self::Error> {
+        let command = MessageFlags::READ
+            .union(MessageFlags::SINGLE)
+            .register::<R>();
+        let mut buffer = [command, 0];
+        self.bus.transfer_in_place(&mut buffer)?;
+        Ok(buffer[1])
+    }
+
+    fn write<R: Register>(&mut self, data: u8) -> nb::Result<(), Self::Error> {
+        let command = MessageFlags::WRITE
+            .union(MessageFlags::SINGLE)
+            .register::<R>();
+        self.bus.write(&[command, data])?;
+        Ok(())
+    }
+
+    fn destroy(self) -> Self::RawBus {
+        self.bus
+    }
+}
+
+/// I2C bus over an embedded-hal 1.0 [`I2c`] implementation. With the `async`
+/// feature it also drives async I2C buses
+pub struct I2CBus<I2C> {
+    bus: I2C,
+    address: Address,
+}
+
+impl<I2C> I2CBus<I2C> {
+    pub fn new(bus: I2C, address: Address) -> Self {
+        Self { bus, address }
+    }
+}
+
+impl<I2C: I2c> DataBus for I2CBus<I2C> {
+    type Error = I2C::Error;
+    type RawBus = I2C;
+
+    fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        self.bus
+            .write_read(self.address.into(), &[R::ADDRESS], buffer)?;
+        Ok(())
+    }
+
+    fn write_all<R: Register>(&mut self, buffer: &[u8]) -> nb::Result<(), Self::Error> {
+        // Adjacent writes go out back to back, without a repeated start
+        self.bus.transaction(
+            self.address.into(),
+            &mut [
+                i2c::Operation::Write(&[R::ADDRESS]),
+                i2c::Operation::Write(buffer),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn read<R: Register>(&mut self) -> nb::Result<u8, Self::Error> {
+        let mut buffer = [0];
+        self.bus
+            .write_read(self.address.into(), &[R::ADDRESS], &mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    fn write<R: Register>(&mut self, data: u8) -> nb::Result<(), Self::Error> {
+        self.bus.write(self.address.into(), &[R::ADDRESS, data])?;
+        Ok(())
+    }
+
+    fn destroy(self) -> Self::RawBus {
+        self.bus
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use embedded_hal_async::{
+        i2c::{self, I2c},
+        spi::{Operation, SpiDevice},
+    };
+
+    use super::{I2CBus, SPIDeviceBus};
+    use crate::{
+        data_bus::{AsyncDataBus, MessageFlags},
+        register::Register,
+    };
+
+    impl<SPI: SpiDevice> AsyncDataBus for SPIDeviceBus<SPI> {
+        type Error = SPI::Error;
+        type RawBus = SPI;
+
+        async fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            if buffer.is_empty() {
+                return Ok(());
+            }
+
+            let command = MessageFlags::READ
+                .union(MessageFlags::MULTIPLE)
+                .register::<R>();
+            self.bus
+                .transaction(&mut [Operation::Write(&[command]), Operation::Read(buffer)])
+                .await
+        }
+
+        async fn write_all<R: Register>(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
+            if buffer.is_empty() {
+                return Ok(());
+            }
+
+            let command = MessageFlags::WRITE
+                .union(MessageFlags::MULTIPLE)
+                .register::<R>();
+            self.bus
+                .transaction(&mut [Operation::Write(&[command]), Operation::Write(buffer)])
+                .await
+        }
+
+        async fn read<R: Register>(&mut self) -> Result<u8, Self::Error> {
+            let command = MessageFlags::READ
+                .union(MessageFlags::SINGLE)
+                .register::<R>();
+            let mut buffer = [command, 0];
+            self.bus.transfer_in_place(&mut buffer).await?;
+            Ok(buffer[1])
+        }
+
+        async fn write<R: Register>(&mut self, data: u8) -> Result<(), Self::Error> {
+            let command = MessageFlags::WRITE
+                .union(MessageFlags::SINGLE)
+                .register::<R>();
+            self.bus.write(&[command, data]).await
+        }
+
+        fn destroy(self) -> Self::RawBus {
+            self.bus
+        }
+    }
+
+    impl<I2C: I2c> AsyncDataBus for I2CBus<I2C> {
+        type Error = I2C::Error;
+        type RawBus = I2C;
+
+        async fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            self.bus
+                .write_read(self.address.into(), &[R::ADDRESS], buffer)
+                .await
+        }
+
+        async fn write_all<R: Register>(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
+            self.bus
+                .transaction(
+                    self.address.into(),
+                    &mut [
+                        i2c::Operation::Write(&[R::ADDRESS]),
+                        i2c::Operation::Write(buffer),
+                    ],
+                )
+                .await
+        }
+
+        async fn read<R: Register>(&mut self) -> Result<u8, Self::Error> {
+            let mut buffer = [0];
+            self.bus
+                .write_read(self.address.into(), &[R::ADDRESS], &mut buffer)
+                .await?;
+            Ok(buffer[0])
+        }
+
+        async fn write<R: Register>(&mut self, data: u8) -> Result<(), Self::Error> {
+            self.bus
+                .write(self.address.into(), &[R::ADDRESS, data])
+                .await
+        }
+
+        fn destroy(self) -> Self::RawBus {
+            self.bus
+        }
+    }
+}
@@ -4,15 +4,17 @@ use arrayvec::ArrayVec;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
 use num_enum::IntoPrimitive;
 
-use super::{DataBus, MessageFlags};
+use super::DataBus;
 
 #[repr(u8)]
-#[derive(Clone, Copy, IntoPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive)]
 pub enum Address {
     Default = 0x1D,
     Alt = 0x53,
 }
 
+/// I2C bus over the blocking `Write` and `WriteRead` traits. Transactions
+/// complete before returning, so `WouldBlock` is never reported
 pub struct I2CBus<I2C> {
     bus: I2C,
     address: Address,
@@ -36,13 +38,7 @@ where
         buffer: &mut [u8],
     ) -> nb::Result<(), Self::Error> {
         self.bus
-            .write_read(
-                self.address.into(),
-                &[MessageFlags::READ
-                    .union(MessageFlags::MULTIPLE)
-                    .register::<R>()],
-                buffer,
-            )
+            .write_read(self.address.into(), &[R::ADDRESS], buffer)
             .map_err(I2CError::WriteRead)?;
         Ok(())
     }
@@ -52,15 +48,11 @@ where
         buffer: &[u8],
     ) -> nb::Result<(), Self::Error> {
         let mut vec = ArrayVec::<_, 14>::new();
-        vec.push(
-            MessageFlags::WRITE
-                .union(MessageFlags::MULTIPLE)
-                .register::<R>(),
-        );
+        vec.push(R::ADDRESS);
         vec.try_extend_from_slice(buffer)
             .map_err(I2CError::Capacity)?;
         self.bus
-            .write(self.address.into(), buffer)
+            .write(self.address.into(), &vec)
             .map_err(I2CError::Write)?;
         Ok(())
     }
@@ -70,9 +62,7 @@ where
         self.bus
             .write_read(
                 self.address.into(),
-                &[MessageFlags::READ
-                    .union(MessageFlags::SINGLE)
-                    .register::<R>()],
+                &[R::ADDRESS],
                 slice::from_mut(&mut buf),
             )
             .map_err(I2CError::WriteRead)?;
@@ -80,12 +70,7 @@ where
     }
 
     fn write<R: crate::register::Register>(&mut self, data: u8) -> nb::Result<(), Self::Error> {
-        let msg = [
-            MessageFlags::WRITE
-                .union(MessageFlags::SINGLE)
-                .register::<R>(),
-            data,
-        ];
+        let msg = [R::ADDRESS, data];
         self.bus
             .write(self.address.into(), &msg)
             .map_err(I2CError::Write)?;
@@ -120,3 +105,19 @@ where
         }
     }
 }
+
+#[cfg(feature = "defmt")]
+impl<I2C> defmt::Format for I2CError<I2C>
+where
+    I2C: Write + WriteRead,
+    <I2C as WriteRead>::Error: defmt::Format,
+    <I2C as Write>::Error: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            I2CError::WriteRead(error) => defmt::write!(f, "WriteRead({})", error),
+            I2CError::Write(error) => defmt::write!(f, "Write({})", error),
+            I2CError::Capacity(_) => defmt::write!(f, "Capacity"),
+        }
+    }
+}
@@ -1,8 +1,14 @@
+mod blocking_spi;
+#[cfg(feature = "eh1")]
+pub mod eh1;
 pub mod i2c;
 mod spi;
+mod three_wire;
 
+pub use blocking_spi::{BlockingSPIBus, BlockingSpiError};
 pub use i2c::I2CBus;
-pub use spi::SPIBus;
+pub use spi::{SPIBus, SpiError, SpiWord};
+pub use three_wire::{HalfDuplex, ThreeWireError, ThreeWireSPIBus};
 
 use bitflags::bitflags;
 
@@ -19,17 +25,32 @@ pub trait DataBus {
     fn destroy(self) -> Self::RawBus;
 }
 
+/// Async counterpart of [`DataBus`]
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncDataBus {
+    type Error;
+    type RawBus;
+    async fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>;
+    async fn write_all<R: Register>(&mut self, buffer: &[u8]) -> Result<(), Self::Error>;
+    async fn read<R: Register>(&mut self) -> Result<u8, Self::Error>;
+    async fn write<R: Register>(&mut self, data: u8) -> Result<(), Self::Error>;
+
+    fn destroy(self) -> Self::RawBus;
+}
+
 bitflags! {
+    /// Command bits prepended to the register address on SPI transactions
     struct MessageFlags: u8 {
-        const READ = 0b1011_1111;
-        const WRITE = 0b0011_1111;
-        const SINGLE = 0b0011_1111;
-        const MULTIPLE = 0b0111_1111;
+        const READ = 0b1000_0000;
+        const WRITE = 0b0000_0000;
+        const SINGLE = 0b0000_0000;
+        const MULTIPLE = 0b0100_0000;
     }
 }
 
 impl MessageFlags {
     pub fn register<R: Register>(self) -> u8 {
-        self.bits & R::ADDRESS
+        self.bits | R::ADDRESS
     }
 }
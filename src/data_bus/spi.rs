@@ -1,180 +1,266 @@
 use core::fmt;
 
 use embedded_hal::{digital::v2::OutputPin, spi};
-use nb::block;
 
-use crate::register::Register;
+use crate::register::{Register, DATAX0, DATAZ1, INT_SOURCE};
 
 use super::{DataBus, MessageFlags};
 pub use raw::SPIBus;
 
 mod raw {
-    use core::{
-        ops::{Deref, DerefMut},
-        panic, marker::PhantomData,
-    };
+    use core::marker::PhantomData;
+
     use embedded_hal::{digital::v2::OutputPin, spi};
-    use nb::block;
 
-    use super::SpiError;
+    use super::{SpiError, SpiWord};
+
+    /// Longest transaction, in bytes: the command byte, every register from
+    /// `THRESH_TAP` to `FIFO_STATUS`, and padding up to a whole word
+    const MAX_TRANSACTION: usize = 32;
+
+    /// 4-wire SPI bus over a `FullDuplex` peripheral.
+    ///
+    /// Transactions are resumable: when the peripheral isn't ready to send or
+    /// receive a word, [`nb::Error::WouldBlock`] is returned with chip select
+    /// still asserted, and calling the same method again with the same
+    /// arguments picks up where it left off. Calling it with anything else
+    /// abandons the transaction and starts the new one.
+    ///
+    /// Only single transactions resume. Driver methods made of several, such
+    /// as [`RegisterHandle::modify`](crate::register::RegisterHandle::modify)
+    /// or the power mode changes, start over from their first transaction
+    /// when called again after `WouldBlock`; drive them with
+    /// [`nb::block!`].
     pub struct SPIBus<SPI, CS: OutputPin, Word> {
         bus: SPI,
         cs: CS,
-        _word: PhantomData<Word>
+        progress: Option<Progress>,
+        _word: PhantomData<Word>,
+    }
+
+    /// Transaction in progress, kept across `WouldBlock`
+    struct Progress {
+        /// Bytes to send, command byte first and zeros after the payload
+        outgoing: [u8; MAX_TRANSACTION],
+        /// Bytes in the transaction, command byte and padding included
+        len: usize,
+        /// Words fully exchanged so far
+        done: usize,
+        /// Whether the next word was sent and its reply is pending
+        sent: bool,
+        received: [u8; MAX_TRANSACTION],
     }
 
     impl<SPI, CS, Word> SPIBus<SPI, CS, Word>
     where
         SPI: spi::FullDuplex<Word>,
         CS: OutputPin,
+        Word: SpiWord,
     {
         pub fn new(bus: SPI, cs: CS) -> Self {
-            Self { bus, cs, _word: PhantomData }
+            Self {
+                bus,
+                cs,
+                progress: None,
+                _word: PhantomData,
+            }
         }
-        pub(super) fn spi_handle(&mut self) -> Result<SPIBusGuard<SPI, CS, Word>, SpiError<SPI, CS, Word>> {
-            SPIBusGuard::new(self)
+
+        /// Exchange `command` followed by `payload`, or by `read_len` zeros
+        /// when there is no payload, and return every byte received. The
+        /// reply to the command byte comes first.
+        pub(super) fn exchange(
+            &mut self,
+            command: u8,
+            payload: Option<&[u8]>,
+            read_len: usize,
+        ) -> nb::Result<[u8; MAX_TRANSACTION], SpiError<SPI, CS, Word>> {
+            let payload_len = payload.map_or(read_len, <[u8]>::len);
+            let len = (1 + payload_len).next_multiple_of(Word::BYTES);
+            if len > MAX_TRANSACTION {
+                return Err(SpiError::Capacity.into());
+            }
+
+            let mut outgoing = [0u8; MAX_TRANSACTION];
+            outgoing[0] = command;
+            if let Some(payload) = payload {
+                outgoing[1..=payload.len()].copy_from_slice(payload);
+            }
+
+            let resuming = matches!(
+                &self.progress,
+                Some(progress) if progress.outgoing == outgoing && progress.len == len
+            );
+            if !resuming {
+                self.abandon()?;
+                self.cs.set_low().map_err(SpiError::ChipSelect)?;
+                self.progress = Some(Progress {
+                    outgoing,
+                    len,
+                    done: 0,
+                    sent: false,
+                    received: [0; MAX_TRANSACTION],
+                });
+            }
+
+            while let Some(progress) = self.progress.as_mut() {
+                let start = progress.done * Word::BYTES;
+                if start >= progress.len {
+                    break;
+                }
+                let range = start..start + Word::BYTES;
+
+                if !progress.sent {
+                    match self
+                        .bus
+                        .send(Word::from_be_bytes(&progress.outgoing[range.clone()]))
+                    {
+                        Ok(()) => progress.sent = true,
+                        Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                        Err(nb::Error::Other(error)) => {
+                            self.abandon()?;
+                            return Err(SpiError::Transfer(error).into());
+                        }
+                    }
+                }
+
+                match self.bus.read() {
+                    Ok(word) => {
+                        word.to_be_bytes(&mut progress.received[range]);
+                        progress.done += 1;
+                        progress.sent = false;
+                    }
+                    Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                    Err(nb::Error::Other(error)) => {
+                        self.abandon()?;
+                        return Err(SpiError::Read(error).into());
+                    }
+                }
+            }
+
+            let received = self.progress.take().map(|progress| progress.received);
+            self.cs.set_high().map_err(SpiError::ChipSelect)?;
+            Ok(received.unwrap_or_default())
+        }
+
+        /// Drop the transaction in progress, if any, releasing chip select
+        fn abandon(&mut self) -> Result<(), SpiError<SPI, CS, Word>> {
+            if self.progress.take().is_some() {
+                self.cs.set_high().map_err(SpiError::ChipSelect)?;
+            }
+            Ok(())
         }
 
         pub(super) fn destroy(self) -> (SPI, CS) {
             (self.bus, self.cs)
         }
     }
-    pub(super) struct SPIBusGuard<'a, SPI, CS: OutputPin, Word>(&'a mut SPIBus<SPI, CS, Word>);
+}
 
-    impl<'a, SPI, CS, Word> SPIBusGuard<'a, SPI, CS, Word>
-    where
-        SPI: spi::FullDuplex<Word>,
-        CS: OutputPin,
-    {
-        fn new(bus: &'a mut SPIBus<SPI, CS, Word>) -> Result<Self, SpiError<SPI, CS, Word>> {
-            bus.cs
-                .set_low()
-                .map(|_| Self(bus))
-                .map_err(SpiError::ChipSelect)
-        }
+/// Word size of a `FullDuplex` SPI peripheral
+pub trait SpiWord: Copy + crate::private::Sealed {
+    const BYTES: usize;
 
-        pub fn exchange(&mut self, word: Word) -> nb::Result<Word, SpiError<SPI, CS, Word>> {
-            self.0
-                .bus
-                .send(word)
-                .map_err(|e| e.map(SpiError::Transfer))?;
-            Ok(block!(self.0.bus.read()).map_err(SpiError::Read)?)
-        }
+    #[doc(hidden)]
+    fn from_be_bytes(bytes: &[u8]) -> Self;
+    #[doc(hidden)]
+    fn to_be_bytes(self, bytes: &mut [u8]);
+}
+
+impl SpiWord for u8 {
+    const BYTES: usize = 1;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        bytes[0]
     }
 
-    impl<'a, SPI, CS: OutputPin, Word> Deref for SPIBusGuard<'a, SPI, CS, Word> {
-        type Target = SPI;
-        fn deref(&self) -> &Self::Target {
-            &self.0.bus
-        }
+    fn to_be_bytes(self, bytes: &mut [u8]) {
+        bytes[0] = self;
     }
+}
 
-    impl<'a, SPI, CS: OutputPin, Word> DerefMut for SPIBusGuard<'a, SPI, CS, Word> {
-        fn deref_mut(&mut self) -> &mut Self::Target {
-            &mut self.0.bus
-        }
+impl crate::private::Sealed for u16 {}
+
+impl SpiWord for u16 {
+    const BYTES: usize = 2;
+
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        u16::from_be_bytes([bytes[0], bytes[1]])
     }
 
-    impl<'a, SPI, CS: OutputPin, Word> Drop for SPIBusGuard<'a, SPI, CS, Word> {
-        fn drop(&mut self) {
-            self.0.cs.set_high().unwrap_or_else(|_| panic!());
-        }
+    fn to_be_bytes(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&u16::to_be_bytes(self));
     }
 }
 
-impl<SPI, CS> DataBus for raw::SPIBus<SPI, CS, u16>
+/// Whether a burst of `len` bytes leaves the last word partly filled, once
+/// the command byte is counted
+// `usize::is_multiple_of` would raise the MSRV to 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn needs_padding<Word: SpiWord>(len: usize) -> bool {
+    (1 + len) % Word::BYTES != 0
+}
+
+impl<SPI, CS, Word> DataBus for raw::SPIBus<SPI, CS, Word>
 where
-    SPI: spi::FullDuplex<u16>,
+    SPI: spi::FullDuplex<Word>,
     CS: OutputPin,
+    Word: SpiWord,
 {
-    type Error = SpiError<SPI, CS, u16>;
+    type Error = SpiError<SPI, CS, Word>;
     type RawBus = (SPI, CS);
     fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
-        let (first, elements) = if let Some((first, elements)) = buffer.split_first_mut() {
-            (first, elements)
-        } else {
+        if buffer.is_empty() {
             return Ok(());
-        };
-
-        let msg = u16::from_be_bytes([
-            MessageFlags::READ
-                .union(MessageFlags::MULTIPLE)
-                .register::<R>(),
-            0,
-        ]);
-
-        let mut spi = self.spi_handle()?;
-
-        *first = spi.exchange(msg).map(|word| word.to_be_bytes()[1])?;
-
-        let mut chunked = elements.chunks_exact_mut(2);
-        for chunk in &mut chunked {
-            let result = block!(spi.exchange(0)).map(|word| word.to_be_bytes())?;
-            chunk.copy_from_slice(&result);
         }
 
-        if let [last] = chunked.into_remainder() {
-            *last = block!(spi.exchange(0)).map(|word| word.to_be_bytes()[0])?;
+        // Padding the last word reads the register after the burst, which
+        // mustn't be one that reading clears or pops
+        if needs_padding::<Word>(buffer.len()) {
+            let padding = usize::from(R::ADDRESS) + buffer.len();
+            let data = usize::from(DATAX0::ADDRESS)..=usize::from(DATAZ1::ADDRESS);
+            if padding == usize::from(INT_SOURCE::ADDRESS) || data.contains(&padding) {
+                return Err(SpiError::InvalidReadBuffer { len: buffer.len() }.into());
+            }
         }
+
+        let msg = MessageFlags::READ
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        let received = self.exchange(msg, None, buffer.len())?;
+        buffer.copy_from_slice(&received[1..=buffer.len()]);
         Ok(())
     }
 
     fn write_all<R: Register>(&mut self, buffer: &[u8]) -> nb::Result<(), Self::Error> {
-        if buffer.len() > 1 && buffer.len() % 2 == 0 {
-            return Err(SpiError::InvalidWriteBuffer("Buffer length must be 0, 1 or odd.").into());
-        }
-        let (first, elements) = if let Some((first, elements)) = buffer.split_first() {
-            (first, elements)
-        } else {
+        if buffer.is_empty() {
             return Ok(());
-        };
-
-        let msg = u16::from_be_bytes([
-            MessageFlags::WRITE
-                .union(MessageFlags::MULTIPLE)
-                .register::<R>(),
-            *first,
-        ]);
-
-        let mut spi = self.spi_handle()?;
-
-        spi.exchange(msg)?;
-
-        let mut chunked = elements.chunks_exact(2);
-        for chunk in &mut chunked {
-            let word = u16::from_be_bytes([chunk[0], chunk[1]]);
-            block!(spi.exchange(word))?;
+        }
+        // Padding the last word would write to the register after the burst
+        if needs_padding::<Word>(buffer.len()) {
+            return Err(SpiError::InvalidWriteBuffer { len: buffer.len() }.into());
         }
 
+        let msg = MessageFlags::WRITE
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        self.exchange(msg, Some(buffer), 0)?;
         Ok(())
     }
 
     fn read<R: Register>(&mut self) -> nb::Result<u8, Self::Error> {
-        let msg = u16::from_be_bytes([
-            MessageFlags::READ
-                .union(MessageFlags::SINGLE)
-                .register::<R>(),
-            0,
-        ]);
-
-        let mut spi = self.spi_handle()?;
-
-        let output = spi.exchange(msg)?;
-        Ok(output as u8)
+        let msg = MessageFlags::READ
+            .union(MessageFlags::SINGLE)
+            .register::<R>();
+        let received = self.exchange(msg, None, 1)?;
+        Ok(received[1])
     }
 
     fn write<R: Register>(&mut self, data: u8) -> nb::Result<(), Self::Error> {
-        let msg = u16::from_be_bytes([
-            MessageFlags::WRITE
-                .union(MessageFlags::SINGLE)
-                .register::<R>(),
-            data,
-        ]);
-
-        let mut spi = self.spi_handle()?;
-
-        spi.exchange(msg)?;
-
+        let msg = MessageFlags::WRITE
+            .union(MessageFlags::SINGLE)
+            .register::<R>();
+        self.exchange(msg, Some(&[data]), 0)?;
         Ok(())
     }
 
@@ -194,8 +280,16 @@ where
     /// SPI error occured during a transfer transaction
     Transfer(<SPI as spi::FullDuplex<Word>>::Error),
 
-    /// SPI error occured during a transfer transaction
-    InvalidWriteBuffer(&'static str),
+    /// Burst of `len` bytes that doesn't fill whole words together with the
+    /// command byte
+    InvalidWriteBuffer { len: usize },
+
+    /// Burst of `len` bytes whose padding would read `INT_SOURCE` or a data
+    /// register, losing interrupts or samples
+    InvalidReadBuffer { len: usize },
+
+    /// Transaction longer than the bus can keep track of
+    Capacity,
 
     /// Error occured while changing chip select signal
     ChipSelect(<CS as OutputPin>::Error),
@@ -213,7 +307,37 @@ where
             SpiError::Read(error) => write!(f, "Read({error:?})"),
             SpiError::Transfer(error) => write!(f, "Transfer({error:?})"),
             SpiError::ChipSelect(error) => write!(f, "ChipSelect({error:?})"),
-            SpiError::InvalidWriteBuffer(error) => write!(f, "InvalidWriteBuffer({error:?})"),
+            SpiError::InvalidWriteBuffer { len } => {
+                write!(f, "InvalidWriteBuffer {{ len: {len} }}")
+            }
+            SpiError::InvalidReadBuffer { len } => {
+                write!(f, "InvalidReadBuffer {{ len: {len} }}")
+            }
+            SpiError::Capacity => write!(f, "Capacity"),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<SPI, CS, Word> defmt::Format for SpiError<SPI, CS, Word>
+where
+    SPI: spi::FullDuplex<Word>,
+    CS: OutputPin,
+    <SPI as spi::FullDuplex<Word>>::Error: defmt::Format,
+    <CS as OutputPin>::Error: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            SpiError::Read(error) => defmt::write!(f, "Read({})", error),
+            SpiError::Transfer(error) => defmt::write!(f, "Transfer({})", error),
+            SpiError::ChipSelect(error) => defmt::write!(f, "ChipSelect({})", error),
+            SpiError::InvalidWriteBuffer { len } => {
+                defmt::write!(f, "InvalidWriteBuffer {{ len: {=usize} }}", len)
+            }
+            SpiError::InvalidReadBuffer { len } => {
+                defmt::write!(f, "InvalidReadBuffer {{ len: {=usize} }}", len)
+            }
+            SpiError::Capacity => defmt::write!(f, "Capacity"),
         }
     }
 }
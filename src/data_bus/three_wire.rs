@@ -0,0 +1,154 @@
+use core::fmt;
+
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::register::Register;
+
+use super::{DataBus, MessageFlags};
+
+/// Bidirectional SPI, where a single data line is either driven by the host
+/// or released for the device to drive
+pub trait HalfDuplex {
+    type Error;
+
+    /// Clock `words` out on the data line
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error>;
+
+    /// Release the data line and clock `words` in
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// 3-wire SPI bus, for a device whose `DATA_FORMAT::spi` bit is set
+pub struct ThreeWireSPIBus<SPI, CS> {
+    bus: SPI,
+    cs: CS,
+}
+
+impl<SPI, CS> ThreeWireSPIBus<SPI, CS>
+where
+    SPI: HalfDuplex,
+    CS: OutputPin,
+{
+    pub fn new(bus: SPI, cs: CS) -> Self {
+        Self { bus, cs }
+    }
+
+    /// Run `f` with chip select asserted. Chip select is released even if
+    /// `f` fails, and the first error is reported
+    fn transaction<F>(&mut self, f: F) -> Result<(), ThreeWireError<SPI, CS>>
+    where
+        F: FnOnce(&mut SPI) -> Result<(), SPI::Error>,
+    {
+        self.cs.set_low().map_err(ThreeWireError::ChipSelect)?;
+        let result = f(&mut self.bus).map_err(ThreeWireError::Transfer);
+        let released = self.cs.set_high().map_err(ThreeWireError::ChipSelect);
+
+        result.and(released)
+    }
+}
+
+impl<SPI, CS> DataBus for ThreeWireSPIBus<SPI, CS>
+where
+    SPI: HalfDuplex,
+    CS: OutputPin,
+{
+    type Error = ThreeWireError<SPI, CS>;
+    type RawBus = (SPI, CS);
+
+    fn read_all<R: Register>(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let msg = MessageFlags::READ
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        self.transaction(|spi| {
+            spi.write(&[msg])?;
+            spi.read(buffer)
+        })?;
+        Ok(())
+    }
+
+    fn write_all<R: Register>(&mut self, buffer: &[u8]) -> nb::Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let msg = MessageFlags::WRITE
+            .union(MessageFlags::MULTIPLE)
+            .register::<R>();
+        self.transaction(|spi| {
+            spi.write(&[msg])?;
+            spi.write(buffer)
+        })?;
+        Ok(())
+    }
+
+    fn read<R: Register>(&mut self) -> nb::Result<u8, Self::Error> {
+        let msg = MessageFlags::READ
+            .union(MessageFlags::SINGLE)
+            .register::<R>();
+        let mut data = [0];
+        self.transaction(|spi| {
+            spi.write(&[msg])?;
+            spi.read(&mut data)
+        })?;
+        Ok(data[0])
+    }
+
+    fn write<R: Register>(&mut self, data: u8) -> nb::Result<(), Self::Error> {
+        let msg = MessageFlags::WRITE
+            .union(MessageFlags::SINGLE)
+            .register::<R>();
+        self.transaction(|spi| spi.write(&[msg, data]))?;
+        Ok(())
+    }
+
+    fn destroy(self) -> Self::RawBus {
+        (self.bus, self.cs)
+    }
+}
+
+pub enum ThreeWireError<SPI, CS>
+where
+    SPI: HalfDuplex,
+    CS: OutputPin,
+{
+    /// Error occured while clocking data in or out
+    Transfer(<SPI as HalfDuplex>::Error),
+
+    /// Error occured while changing chip select signal
+    ChipSelect(<CS as OutputPin>::Error),
+}
+
+impl<SPI, CS> fmt::Debug for ThreeWireError<SPI, CS>
+where
+    SPI: HalfDuplex,
+    CS: OutputPin,
+    <SPI as HalfDuplex>::Error: fmt::Debug,
+    <CS as OutputPin>::Error: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreeWireError::Transfer(error) => write!(f, "Transfer({error:?})"),
+            ThreeWireError::ChipSelect(error) => write!(f, "ChipSelect({error:?})"),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<SPI, CS> defmt::Format for ThreeWireError<SPI, CS>
+where
+    SPI: HalfDuplex,
+    CS: OutputPin,
+    <SPI as HalfDuplex>::Error: defmt::Format,
+    <CS as OutputPin>::Error: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            ThreeWireError::Transfer(error) => defmt::write!(f, "Transfer({})", error),
+            ThreeWireError::ChipSelect(error) => defmt::write!(f, "ChipSelect({})", error),
+        }
+    }
+}
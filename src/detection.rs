@@ -0,0 +1,268 @@
+use crate::{
+    config::{to_lsb, Axes},
+    data_bus::DataBus,
+    error::Error,
+    power::Standby,
+    register::{
+        act_tap_status, int_source, DUR, THRESH_ACT, THRESH_FF, THRESH_INACT, THRESH_TAP, TIME_FF,
+        TIME_INACT,
+    },
+    ADXL345,
+};
+
+/// Single and double tap detection, in physical units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TapConfig {
+    /// Acceleration threshold, up to 15 937 mg in steps of 62.5 mg
+    pub threshold_mg: u16,
+    /// Maximum time above the threshold to count as a tap, up to 159 375 µs
+    /// in steps of 625 µs. Zero disables tap detection
+    pub duration_us: u32,
+    /// Wait from a tap to the start of the double tap window, up to 318 ms
+    /// in steps of 1.25 ms. Zero disables double tap detection
+    pub latency_ms: u16,
+    /// Time in which a second tap counts as a double tap, up to 318 ms in
+    /// steps of 1.25 ms. Zero disables double tap detection
+    pub window_ms: u16,
+    /// Axes taking part in tap detection
+    pub axes: Axes,
+    /// Suppress double taps when acceleration stays above the threshold
+    /// between taps
+    pub suppress: bool,
+}
+
+impl TapConfig {
+    /// Contents of `THRESH_TAP` and of the contiguous `DUR`, `Latent` and
+    /// `Window` registers
+    fn registers<E>(&self) -> Result<(u8, [u8; 3]), Error<E>> {
+        Ok((
+            to_lsb(self.threshold_mg.into(), 125, 2, "threshold_mg")?,
+            [
+                to_lsb(self.duration_us, 625, 1, "duration_us")?,
+                to_lsb(self.latency_ms.into(), 5, 4, "latency_ms")?,
+                to_lsb(self.window_ms.into(), 5, 4, "window_ms")?,
+            ],
+        ))
+    }
+}
+
+/// Tap reported by the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TapEvent {
+    /// Single tap, first detected on `axis`
+    Single { axis: Axes },
+    /// Double tap, first detected on `axis`
+    Double { axis: Axes },
+}
+
+impl TapEvent {
+    /// Decode a tap out of `INT_SOURCE` and `ACT_TAP_STATUS`.
+    ///
+    /// `ACT_TAP_STATUS` must be read before `INT_SOURCE`, since reading the
+    /// latter clears the interrupts and lets the status change.
+    pub fn decode(source: &int_source::Handle, status: &act_tap_status::Handle) -> Option<Self> {
+        let axis = Axes::from_enables(
+            status.tap_x_source(),
+            status.tap_y_source(),
+            status.tap_z_source(),
+        );
+
+        if source.double_tap() {
+            Some(TapEvent::Double { axis })
+        } else if source.single_tap() {
+            Some(TapEvent::Single { axis })
+        } else {
+            None
+        }
+    }
+}
+
+/// How acceleration is compared against the activity and inactivity
+/// thresholds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Coupling {
+    /// Compare the acceleration itself
+    #[default]
+    Dc,
+    /// Compare the change in acceleration from a reference taken when
+    /// detection starts
+    Ac,
+}
+
+/// Activity detection, in physical units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActivityConfig {
+    /// Acceleration threshold, up to 15 937 mg in steps of 62.5 mg
+    pub threshold_mg: u16,
+    /// Axes taking part in activity detection
+    pub axes: Axes,
+    /// Whether the threshold applies to the acceleration or to its change
+    /// since activity detection was enabled
+    pub coupling: Coupling,
+}
+
+/// Inactivity detection, in physical units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InactivityConfig {
+    /// Acceleration threshold, up to 15 937 mg in steps of 62.5 mg
+    pub threshold_mg: u16,
+    /// Time acceleration must stay below the threshold, in seconds
+    pub time_s: u8,
+    /// Axes taking part in inactivity detection
+    pub axes: Axes,
+    /// Whether the threshold applies to the acceleration or to its change
+    /// from a reference, which the device moves to the current acceleration
+    /// whenever the change exceeds the threshold
+    pub coupling: Coupling,
+}
+
+/// Activity reported by the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActivityEvent {
+    /// Axes first detected above the threshold
+    pub axis: Axes,
+}
+
+impl ActivityEvent {
+    /// Decode activity out of `INT_SOURCE` and `ACT_TAP_STATUS`.
+    ///
+    /// `ACT_TAP_STATUS` must be read before `INT_SOURCE`, since reading the
+    /// latter clears the interrupts and lets the status change.
+    pub fn decode(source: &int_source::Handle, status: &act_tap_status::Handle) -> Option<Self> {
+        source.activity().then(|| ActivityEvent {
+            axis: Axes::from_enables(
+                status.act_x_source(),
+                status.act_y_source(),
+                status.act_z_source(),
+            ),
+        })
+    }
+}
+
+/// Free-fall detection, in physical units.
+///
+/// Values are limited to the ranges recommended by the datasheet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FreeFallConfig {
+    /// Threshold all axes must stay below, 300 to 600 mg in steps of 62.5 mg
+    pub threshold_mg: u16,
+    /// Time all axes must stay below the threshold, 100 to 350 ms in steps
+    /// of 5 ms
+    pub time_ms: u16,
+}
+
+impl FreeFallConfig {
+    /// Contents of `THRESH_FF` and `TIME_FF`
+    fn registers<E>(&self) -> Result<(u8, u8), Error<E>> {
+        if !(300..=600).contains(&self.threshold_mg) {
+            return Err(Error::OutOfRange {
+                field: "threshold_mg",
+                value: self.threshold_mg.into(),
+            });
+        }
+        if !(100..=350).contains(&self.time_ms) {
+            return Err(Error::OutOfRange {
+                field: "time_ms",
+                value: self.time_ms.into(),
+            });
+        }
+
+        Ok((
+            to_lsb(self.threshold_mg.into(), 125, 2, "threshold_mg")?,
+            to_lsb(self.time_ms.into(), 5, 1, "time_ms")?,
+        ))
+    }
+}
+
+/// Free fall reported by the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FreeFallEvent;
+
+impl FreeFallEvent {
+    /// Decode a free fall out of `INT_SOURCE`
+    pub fn decode(source: &int_source::Handle) -> Option<Self> {
+        source.free_fall().then_some(FreeFallEvent)
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Standby, INT> {
+    /// Set up single and double tap detection
+    pub fn configure_tap(&mut self, tap: &TapConfig) -> nb::Result<(), Error<BUS::Error>> {
+        let (threshold, timing) = tap.registers()?;
+
+        self.bus
+            .write::<THRESH_TAP>(threshold)
+            .map_err(|e| e.map(Error::bus::<THRESH_TAP>))?;
+        self.bus
+            .write_all::<DUR>(&timing)
+            .map_err(|e| e.map(Error::bus::<DUR>))?;
+        self.tap_axes().write(|w| {
+            w.set_suppress(tap.suppress)
+                .set_tap_x_enable(tap.axes.contains(Axes::X))
+                .set_tap_y_enable(tap.axes.contains(Axes::Y))
+                .set_tap_z_enable(tap.axes.contains(Axes::Z))
+        })?;
+
+        Ok(())
+    }
+
+    /// Set up activity detection, leaving inactivity detection untouched
+    pub fn configure_activity(
+        &mut self,
+        activity: &ActivityConfig,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let threshold = to_lsb(activity.threshold_mg.into(), 125, 2, "threshold_mg")?;
+
+        self.bus
+            .write::<THRESH_ACT>(threshold)
+            .map_err(|e| e.map(Error::bus::<THRESH_ACT>))?;
+        self.act_inact_ctl().modify(|w| {
+            w.set_act_ac_dc(activity.coupling == Coupling::Ac)
+                .set_act_x_enable(activity.axes.contains(Axes::X))
+                .set_act_y_enable(activity.axes.contains(Axes::Y))
+                .set_act_z_enable(activity.axes.contains(Axes::Z))
+        })?;
+
+        Ok(())
+    }
+
+    /// Set up inactivity detection, leaving activity detection untouched
+    pub fn configure_inactivity(
+        &mut self,
+        inactivity: &InactivityConfig,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let threshold = to_lsb(inactivity.threshold_mg.into(), 125, 2, "threshold_mg")?;
+
+        self.bus
+            .write::<THRESH_INACT>(threshold)
+            .map_err(|e| e.map(Error::bus::<THRESH_INACT>))?;
+        self.bus
+            .write::<TIME_INACT>(inactivity.time_s)
+            .map_err(|e| e.map(Error::bus::<TIME_INACT>))?;
+        self.act_inact_ctl().modify(|w| {
+            w.set_inact_ac_dc(inactivity.coupling == Coupling::Ac)
+                .set_inact_x_enable(inactivity.axes.contains(Axes::X))
+                .set_inact_y_enable(inactivity.axes.contains(Axes::Y))
+                .set_inact_z_enable(inactivity.axes.contains(Axes::Z))
+        })?;
+
+        Ok(())
+    }
+
+    /// Set up free-fall detection
+    pub fn configure_free_fall(
+        &mut self,
+        free_fall: &FreeFallConfig,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let (threshold, time) = free_fall.registers()?;
+
+        self.bus
+            .write::<THRESH_FF>(threshold)
+            .map_err(|e| e.map(Error::bus::<THRESH_FF>))?;
+        self.bus
+            .write::<TIME_FF>(time)
+            .map_err(|e| e.map(Error::bus::<TIME_FF>))?;
+
+        Ok(())
+    }
+}
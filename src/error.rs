@@ -0,0 +1,87 @@
+//! Error reported by the driver, on top of the errors of each bus
+
+use core::fmt;
+
+use crate::{config::Axes, register::Register};
+
+/// Error reported by the driver, where `E` is the error of the bus
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Error occured while accessing the register at `register`
+    Bus { register: u8, source: E },
+    /// `DEVID` didn't hold the ADXL345 device ID
+    InvalidDeviceId(u8),
+    /// `field` can't be represented by its register. `value` is the one
+    /// given, or the register value computed from it
+    OutOfRange { field: &'static str, value: i32 },
+    /// The named function, required by this one, hasn't been set up
+    NotConfigured(&'static str),
+    /// The FIFO filled up and samples were lost
+    FifoOverrun,
+    /// The self-test change was outside the datasheet limits on these axes
+    SelfTestFailed(Axes),
+}
+
+impl<E> Error<E> {
+    /// Wrap an error that occured while accessing `R`
+    pub(crate) fn bus<R: Register>(source: E) -> Self {
+        Error::Bus {
+            register: R::ADDRESS,
+            source,
+        }
+    }
+
+    /// Register being accessed when the bus failed, if the bus failed
+    pub fn register(&self) -> Option<u8> {
+        match self {
+            Error::Bus { register, .. } => Some(*register),
+            _ => None,
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Bus { register, source } => {
+                write!(f, "bus error at register {register:#04x}: {source:?}")
+            }
+            Error::InvalidDeviceId(id) => write!(f, "unexpected device ID {id:#04x}"),
+            Error::OutOfRange { field, value } => write!(f, "{field} = {value} is out of range"),
+            Error::NotConfigured(name) => write!(f, "{name} isn't configured"),
+            Error::FifoOverrun => write!(f, "FIFO overrun"),
+            Error::SelfTestFailed(axes) => write!(f, "self-test failed on {axes:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::Bus { register, source } => {
+                defmt::write!(
+                    f,
+                    "Bus {{ register: {=u8:#04x}, source: {} }}",
+                    register,
+                    source
+                )
+            }
+            Error::InvalidDeviceId(id) => defmt::write!(f, "InvalidDeviceId({=u8:#04x})", id),
+            Error::OutOfRange { field, value } => defmt::write!(
+                f,
+                "OutOfRange {{ field: {=str}, value: {=i32} }}",
+                field,
+                value
+            ),
+            Error::NotConfigured(name) => defmt::write!(f, "NotConfigured({=str})", name),
+            Error::FifoOverrun => defmt::write!(f, "FifoOverrun"),
+            Error::SelfTestFailed(axes) => {
+                defmt::write!(f, "SelfTestFailed({=u8:#05b})", axes.bits())
+            }
+        }
+    }
+}
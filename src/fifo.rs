@@ -0,0 +1,163 @@
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::{
+    data_bus::DataBus,
+    error::Error,
+    interrupt::IntPin,
+    power::{Measuring, Standby},
+    register::FIFOMode,
+    sample::RawSample,
+    ADXL345,
+};
+
+/// Number of samples the FIFO can hold
+pub const FIFO_DEPTH: usize = 32;
+
+/// Minimum time between the end of a data register read and the next read of
+/// the FIFO or of `FIFO_STATUS`, in µs
+pub(crate) const FIFO_READ_GAP_US: u32 = 5;
+
+/// Samples held by the FIFO around a trigger event, in trigger mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerCapture {
+    samples: [RawSample; FIFO_DEPTH],
+    len: usize,
+    pre_trigger: usize,
+}
+
+impl TriggerCapture {
+    /// Every captured sample, oldest first
+    pub fn samples(&self) -> &[RawSample] {
+        &self.samples[..self.len]
+    }
+
+    /// Samples taken before the trigger event
+    pub fn pre_trigger(&self) -> &[RawSample] {
+        &self.samples[..self.pre_trigger]
+    }
+
+    /// Samples taken after the trigger event
+    pub fn post_trigger(&self) -> &[RawSample] {
+        &self.samples[self.pre_trigger..self.len]
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Standby, INT> {
+    /// Set the FIFO mode and the number of samples that raise the watermark
+    /// interrupt, up to 31. The trigger pin is left untouched
+    pub fn configure_fifo(
+        &mut self,
+        mode: FIFOMode,
+        watermark: u8,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        if usize::from(watermark) >= FIFO_DEPTH {
+            return Err(Error::OutOfRange {
+                field: "watermark",
+                value: watermark.into(),
+            }
+            .into());
+        }
+
+        self.fifo_ctl()
+            .modify(|w| w.set_fifo_mode(mode).set_samples(watermark))?;
+
+        Ok(())
+    }
+
+    /// Arm trigger mode, so that once the interrupt routed to `pin` fires
+    /// the FIFO keeps the last `pre_trigger` samples, up to 31, and fills
+    /// up with the ones that follow.
+    ///
+    /// Any previous capture is discarded. The interrupt acting as trigger
+    /// must be enabled and routed to `pin` separately.
+    pub fn arm_trigger(
+        &mut self,
+        pin: IntPin,
+        pre_trigger: u8,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        if usize::from(pre_trigger) >= FIFO_DEPTH {
+            return Err(Error::OutOfRange {
+                field: "pre_trigger",
+                value: pre_trigger.into(),
+            }
+            .into());
+        }
+
+        // Going through bypass mode clears both the FIFO and the trigger
+        self.fifo_ctl()
+            .modify(|w| w.set_fifo_mode(FIFOMode::Bypass))?;
+        self.fifo_ctl().write(|w| {
+            w.set_fifo_mode(FIFOMode::Trigger)
+                .set_trigger(pin == IntPin::Int2)
+                .set_samples(pre_trigger)
+        })?;
+
+        Ok(())
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Measuring, INT> {
+    /// Move the samples stored in the FIFO into `samples`, oldest first, and
+    /// return how many were read.
+    ///
+    /// Only as many samples as `FIFO_STATUS` reported on entry are read, at
+    /// most `samples.len()`. `delay` spaces the reads as the datasheet
+    /// requires.
+    ///
+    /// Overruns are not checked, as reading `INT_SOURCE` would also clear the
+    /// latched tap, activity and free-fall events. Call
+    /// [`ADXL345::poll_events`] first and
+    /// [`Events::check_overrun`](crate::Events::check_overrun) on the result
+    /// to find out whether samples were lost.
+    pub fn read_fifo<D: DelayUs<u32>>(
+        &mut self,
+        samples: &mut [RawSample],
+        delay: &mut D,
+    ) -> nb::Result<usize, Error<BUS::Error>> {
+        let entries = self.fifo_status().read()?.entries();
+        self.drain_fifo(entries, samples, delay)
+    }
+
+    /// Collect the samples around the trigger event, once `FIFO_STATUS`
+    /// reports the trigger and the FIFO has filled up. Until then
+    /// [`nb::Error::WouldBlock`] is returned.
+    ///
+    /// The FIFO is left empty; go back to standby and call
+    /// [`ADXL345::arm_trigger`] again to wait for a new event.
+    pub fn capture_trigger<D: DelayUs<u32>>(
+        &mut self,
+        delay: &mut D,
+    ) -> nb::Result<TriggerCapture, Error<BUS::Error>> {
+        let status = self.fifo_status().read()?;
+        if !status.fifo_trig() || usize::from(status.entries()) < FIFO_DEPTH {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let pre_trigger = usize::from(self.fifo_ctl().read()?.samples());
+
+        let mut samples = [RawSample::default(); FIFO_DEPTH];
+        let len = self.drain_fifo(status.entries(), &mut samples, delay)?;
+
+        Ok(TriggerCapture {
+            samples,
+            len,
+            pre_trigger: pre_trigger.min(len),
+        })
+    }
+
+    fn drain_fifo<D: DelayUs<u32>>(
+        &mut self,
+        entries: u8,
+        samples: &mut [RawSample],
+        delay: &mut D,
+    ) -> nb::Result<usize, Error<BUS::Error>> {
+        let count = usize::from(entries).min(samples.len());
+
+        for sample in &mut samples[..count] {
+            *sample = self.read_raw()?;
+            delay.delay_us(FIFO_READ_GAP_US);
+        }
+
+        Ok(count)
+    }
+}
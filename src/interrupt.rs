@@ -0,0 +1,244 @@
+use core::{convert::Infallible, marker::PhantomData};
+
+use bitflags::bitflags;
+use embedded_hal::digital::v2::InputPin;
+
+use crate::{
+    data_bus::DataBus,
+    detection::{ActivityEvent, FreeFallEvent, TapEvent},
+    error::Error,
+    power::{Measuring, Standby},
+    register::{act_tap_status, int_source, Readable, DATA_FORMAT, INT_ENABLE, INT_MAP},
+    ADXL345,
+};
+
+bitflags! {
+    /// Interrupt sources, laid out as in `INT_ENABLE`, `INT_MAP` and
+    /// `INT_SOURCE`
+    #[derive(Default)]
+    pub struct Interrupt: u8 {
+        const DATA_READY = 0b1000_0000;
+        const SINGLE_TAP = 0b0100_0000;
+        const DOUBLE_TAP = 0b0010_0000;
+        const ACTIVITY = 0b0001_0000;
+        const INACTIVITY = 0b0000_1000;
+        const FREE_FALL = 0b0000_0100;
+        const WATERMARK = 0b0000_0010;
+        const OVERRUN = 0b0000_0001;
+    }
+}
+
+/// Interrupt output pins of the device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntPin {
+    #[default]
+    Int1,
+    Int2,
+}
+
+/// Stands for the interrupt pin of a driver that doesn't own one, in which
+/// case `INT_SOURCE` is polled over the bus instead
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoPin;
+
+/// Interrupt line the driver can sample instead of polling `INT_SOURCE`
+pub trait IntLine {
+    type Error;
+
+    /// Whether the line is asserted, or `None` if there is no line to sample
+    fn asserted(&self, active_low: bool) -> Option<Result<bool, Self::Error>>;
+}
+
+impl IntLine for NoPin {
+    type Error = Infallible;
+
+    fn asserted(&self, _active_low: bool) -> Option<Result<bool, Self::Error>> {
+        None
+    }
+}
+
+impl<P: InputPin> IntLine for P {
+    type Error = P::Error;
+
+    fn asserted(&self, active_low: bool) -> Option<Result<bool, Self::Error>> {
+        Some(if active_low {
+            self.is_low()
+        } else {
+            self.is_high()
+        })
+    }
+}
+
+/// Error returned while waiting on an interrupt
+#[derive(Debug)]
+pub enum WaitError<BUS, PIN> {
+    /// Error occured while talking to the device
+    Bus(BUS),
+    /// Error occured while sampling the interrupt pin
+    Pin(PIN),
+}
+
+/// Events reported by a single read of `INT_SOURCE`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Events {
+    /// Every interrupt that was pending
+    pub interrupts: Interrupt,
+    /// Single or double tap, with the first axis involved. `None` unless
+    /// the tap interrupts are enabled and one of them fired
+    pub tap: Option<TapEvent>,
+    /// Activity, with the first axis involved. `None` unless the activity
+    /// interrupt is enabled and fired
+    pub activity: Option<ActivityEvent>,
+    /// Free fall. `None` unless the free-fall interrupt is enabled and fired
+    pub free_fall: Option<FreeFallEvent>,
+    /// The device was in sleep mode
+    pub asleep: bool,
+}
+
+impl Events {
+    /// Decode `INT_SOURCE` and `ACT_TAP_STATUS`, the latter read first
+    pub fn decode(source: int_source::Handle, status: act_tap_status::Handle) -> Self {
+        Self {
+            tap: TapEvent::decode(&source, &status),
+            activity: ActivityEvent::decode(&source, &status),
+            free_fall: FreeFallEvent::decode(&source),
+            asleep: status.asleep(),
+            interrupts: Interrupt::from(source),
+        }
+    }
+
+    /// Fail with [`Error::FifoOverrun`] if samples were lost because the FIFO
+    /// was full
+    pub fn check_overrun<E>(&self) -> Result<(), Error<E>> {
+        if self.interrupts.contains(Interrupt::OVERRUN) {
+            Err(Error::FifoOverrun)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Standby, INT> {
+    /// Enable exactly the given interrupts, disabling the rest
+    pub fn enable_interrupts(
+        &mut self,
+        interrupts: Interrupt,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        self.bus
+            .write::<INT_ENABLE>(interrupts.bits())
+            .map_err(|e| e.map(Error::bus::<INT_ENABLE>))
+    }
+
+    /// Route the given interrupts to `pin`, leaving the rest where they are
+    pub fn route_interrupts(
+        &mut self,
+        interrupts: Interrupt,
+        pin: IntPin,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let mut int2 = Interrupt::from(self.int_map().read()?);
+        int2.set(interrupts, pin == IntPin::Int2);
+        self.bus
+            .write::<INT_MAP>(int2.bits())
+            .map_err(|e| e.map(Error::bus::<INT_MAP>))
+    }
+}
+
+impl<BUS: DataBus, MODE, INT> ADXL345<BUS, MODE, INT> {
+    /// Read and clear the pending interrupts, together with the tap and
+    /// activity details in `ACT_TAP_STATUS`.
+    ///
+    /// `ACT_TAP_STATUS` is read first, as reading `INT_SOURCE` lets it
+    /// change.
+    pub fn poll_events(&mut self) -> nb::Result<Events, Error<BUS::Error>> {
+        let status = self.act_tap_status().read()?;
+        let source = self.int_source().read()?;
+
+        Ok(Events::decode(source, status))
+    }
+}
+
+impl<BUS, MODE> ADXL345<BUS, MODE> {
+    /// Sample `pin` to find out about interrupts, instead of polling
+    /// `INT_SOURCE`. Interrupts waited on must be routed to that pin
+    pub fn with_int_pin<PIN: InputPin>(self, pin: PIN) -> ADXL345<BUS, MODE, PIN> {
+        ADXL345 {
+            bus: self.bus,
+            data_format: self.data_format,
+            calibration: self.calibration,
+            int_pin: pin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<BUS, MODE, PIN: InputPin> ADXL345<BUS, MODE, PIN> {
+    /// Give the interrupt pin back, going back to polling `INT_SOURCE`
+    pub fn release_int_pin(self) -> (ADXL345<BUS, MODE>, PIN) {
+        (
+            ADXL345 {
+                bus: self.bus,
+                data_format: self.data_format,
+                calibration: self.calibration,
+                int_pin: NoPin,
+                _mode: PhantomData,
+            },
+            self.int_pin,
+        )
+    }
+}
+
+impl<BUS: DataBus, INT: IntLine> ADXL345<BUS, Measuring, INT> {
+    /// Wait for a new sample.
+    ///
+    /// With an interrupt pin, `DATA_READY` is expected to be its only
+    /// interrupt. Otherwise `INT_SOURCE` is read, which clears any pending
+    /// tap, activity, inactivity and free-fall interrupts.
+    pub fn wait_for_data_ready(
+        &mut self,
+    ) -> nb::Result<(), WaitError<Error<BUS::Error>, INT::Error>> {
+        let ready = match self.int_line_asserted()? {
+            Some(asserted) => asserted,
+            None => self
+                .int_source()
+                .read()
+                .map_err(|e| e.map(WaitError::Bus))?
+                .data_ready(),
+        };
+
+        if ready {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<BUS: DataBus, MODE, INT: IntLine> ADXL345<BUS, MODE, INT> {
+    /// Wait for any enabled interrupt and return the decoded events.
+    ///
+    /// With an interrupt pin the bus is only used once the pin is asserted.
+    pub fn wait_for_event(
+        &mut self,
+    ) -> nb::Result<Events, WaitError<Error<BUS::Error>, INT::Error>> {
+        if self.int_line_asserted()? == Some(false) {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let events = self.poll_events().map_err(|e| e.map(WaitError::Bus))?;
+        if events.interrupts.is_empty() {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(events)
+        }
+    }
+
+    /// Sample the interrupt pin, honouring the polarity set in `DATA_FORMAT`
+    #[allow(clippy::type_complexity)]
+    fn int_line_asserted(&self) -> Result<Option<bool>, WaitError<Error<BUS::Error>, INT::Error>> {
+        let active_low = DATA_FORMAT::fill(self.data_format).int_invert();
+        self.int_pin
+            .asserted(active_low)
+            .transpose()
+            .map_err(WaitError::Pin)
+    }
+}
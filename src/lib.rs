@@ -1,21 +1,95 @@
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+use core::marker::PhantomData;
 
 use data_bus::DataBus;
-use embedded_hal::{blocking::i2c, digital::v2::OutputPin, spi};
+use embedded_hal::{
+    blocking::{self, i2c},
+    digital::v2::OutputPin,
+    spi,
+};
+use register::{Writable, DATA_FORMAT};
 
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod calibration;
+pub mod config;
 pub mod data_bus;
+pub mod detection;
+pub mod error;
+pub mod fifo;
+pub mod interrupt;
+pub mod power;
 pub mod register;
+pub mod sample;
+pub mod self_test;
+
+#[cfg(feature = "async")]
+pub use asynch::ADXL345Async;
+pub use calibration::{Calibration, Orientation, SixPositionCalibration};
+pub use config::{Adxl345Config, Axes};
+pub use detection::{
+    ActivityConfig, ActivityEvent, Coupling, FreeFallConfig, FreeFallEvent, InactivityConfig,
+    TapConfig, TapEvent,
+};
+pub use error::Error;
+pub use fifo::TriggerCapture;
+pub use interrupt::{Events, IntLine, IntPin, Interrupt, NoPin, WaitError};
+pub use power::{Measuring, PowerState, Standby};
+pub use sample::{RawSample, Sample, Scale};
+pub use self_test::SelfTestReport;
+
+/// Value of `DEVID` on every ADXL345
+pub const DEVICE_ID: u8 = 0xE5;
 
-pub struct ADXL345<BUS> {
+/// Driver for the device.
+///
+/// `MODE` tracks whether the device is in [`Standby`], where it can be
+/// configured, or [`Measuring`], where samples can be read. Drivers start
+/// out in standby, as the device does on power-on.
+pub struct ADXL345<BUS, MODE = Standby, INT = NoPin> {
     bus: BUS,
+    /// Last value written to or read from `DATA_FORMAT`
+    data_format: u8,
+    /// Software calibration applied to scaled reads
+    calibration: Option<Calibration>,
+    int_pin: INT,
+    _mode: PhantomData<MODE>,
 }
 
-impl<BUS: DataBus> ADXL345<BUS> {
+impl<BUS: DataBus, MODE, INT> ADXL345<BUS, MODE, INT> {
     pub fn destroy(self) -> BUS::RawBus {
         self.bus.destroy()
     }
 }
 
+impl<BUS, MODE, INT> ADXL345<BUS, MODE, INT> {
+    fn into_mode<M>(self) -> ADXL345<BUS, M, INT> {
+        ADXL345 {
+            bus: self.bus,
+            data_format: self.data_format,
+            calibration: self.calibration,
+            int_pin: self.int_pin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Standby, INT> {
+    /// Check that `DEVID` holds [`DEVICE_ID`], so that a miswired or missing
+    /// device is caught before any other access. The constructors don't
+    /// send anything, so this is the way to check the wiring up front.
+    ///
+    /// On failure the bus is handed back.
+    pub fn verify_device_id(mut self) -> Result<Self, (BUS::RawBus, Error<BUS::Error>)> {
+        match nb::block!(self.devid().read()) {
+            Ok(devid) if devid.value() == DEVICE_ID => Ok(self),
+            Ok(devid) => Err((self.destroy(), Error::InvalidDeviceId(devid.value()))),
+            Err(error) => Err((self.destroy(), error)),
+        }
+    }
+}
+
 impl<SPI, CS> ADXL345<data_bus::SPIBus<SPI, CS, u16>>
 where
     SPI: spi::FullDuplex<u16>,
@@ -24,6 +98,123 @@ where
     pub fn from_spi_cs(bus: SPI, cs: CS) -> Self {
         Self {
             bus: data_bus::SPIBus::new(bus, cs),
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Same as [`ADXL345::from_spi_cs`], checking the device ID first with
+    /// [`ADXL345::verify_device_id`]
+    #[allow(clippy::type_complexity)]
+    pub fn probe_spi(
+        bus: SPI,
+        cs: CS,
+    ) -> Result<
+        Self,
+        (
+            (SPI, CS),
+            Error<<data_bus::SPIBus<SPI, CS, u16> as DataBus>::Error>,
+        ),
+    > {
+        Self::from_spi_cs(bus, cs).verify_device_id()
+    }
+}
+
+impl<SPI, CS> ADXL345<data_bus::SPIBus<SPI, CS, u8>>
+where
+    SPI: spi::FullDuplex<u8>,
+    CS: OutputPin,
+{
+    /// Same as [`ADXL345::from_spi_cs`], for SPI peripherals limited to 8-bit
+    /// words
+    pub fn from_spi_cs_u8(bus: SPI, cs: CS) -> Self {
+        Self {
+            bus: data_bus::SPIBus::new(bus, cs),
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<SPI, CS> ADXL345<data_bus::BlockingSPIBus<SPI, CS>>
+where
+    SPI: blocking::spi::Transfer<u8> + blocking::spi::Write<u8>,
+    CS: OutputPin,
+{
+    /// Talk to the device over the blocking SPI traits, moving each register
+    /// burst in a single transfer
+    pub fn from_blocking_spi_cs(bus: SPI, cs: CS) -> Self {
+        Self {
+            bus: data_bus::BlockingSPIBus::new(bus, cs),
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<SPI, CS, Word, INT> ADXL345<data_bus::SPIBus<SPI, CS, Word>, Standby, INT>
+where
+    CS: OutputPin,
+    data_bus::SPIBus<SPI, CS, Word>: DataBus<RawBus = (SPI, CS)>,
+{
+    /// Switch the device to 3-wire SPI and keep talking to it over the
+    /// half-duplex bus `into_half_duplex` makes out of the SPI peripheral.
+    ///
+    /// `DATA_FORMAT::spi` is set over the current 4-wire bus, which stops
+    /// working right after. On failure the driver is handed back unchanged.
+    #[allow(clippy::type_complexity, clippy::result_large_err)]
+    pub fn into_three_wire<H, F>(
+        mut self,
+        into_half_duplex: F,
+    ) -> Result<
+        ADXL345<data_bus::ThreeWireSPIBus<H, CS>, Standby, INT>,
+        (
+            Self,
+            Error<<data_bus::SPIBus<SPI, CS, Word> as DataBus>::Error>,
+        ),
+    >
+    where
+        H: data_bus::HalfDuplex,
+        F: FnOnce(SPI) -> H,
+    {
+        if let Err(error) = nb::block!(self.data_format().modify(|w| w.set_spi(true))) {
+            return Err((self, error));
+        }
+
+        let (spi, cs) = self.bus.destroy();
+        Ok(ADXL345 {
+            bus: data_bus::ThreeWireSPIBus::new(into_half_duplex(spi), cs),
+            data_format: self.data_format,
+            calibration: self.calibration,
+            int_pin: self.int_pin,
+            _mode: PhantomData,
+        })
+    }
+}
+
+impl<SPI, CS> ADXL345<data_bus::ThreeWireSPIBus<SPI, CS>>
+where
+    SPI: data_bus::HalfDuplex,
+    CS: OutputPin,
+{
+    /// Talk to a device already switched to 3-wire SPI, such as one left so
+    /// by [`ADXL345::into_three_wire`] before a reset of the host
+    pub fn from_three_wire(bus: SPI, cs: CS) -> Self {
+        let mut data_format = register::data_format::Handle::default();
+        data_format.set_spi(true);
+
+        Self {
+            bus: data_bus::ThreeWireSPIBus::new(bus, cs),
+            data_format: DATA_FORMAT::into_raw(data_format),
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
         }
     }
 }
@@ -35,6 +226,87 @@ where
     pub fn from_i2c(bus: I2C, address: data_bus::i2c::Address) -> Self {
         Self {
             bus: data_bus::I2CBus::new(bus, address),
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Same as [`ADXL345::from_i2c`], checking the device ID first with
+    /// [`ADXL345::verify_device_id`]
+    #[allow(clippy::type_complexity)]
+    pub fn probe_i2c(
+        bus: I2C,
+        address: data_bus::i2c::Address,
+    ) -> Result<Self, (I2C, Error<data_bus::i2c::I2CError<I2C>>)> {
+        Self::from_i2c(bus, address).verify_device_id()
+    }
+
+    /// Probe [`Address::Default`] and then [`Address::Alt`], returning the
+    /// driver for the first one where the device answered, together with
+    /// that address.
+    ///
+    /// If neither did, a wrong device ID is reported over a bus error, as it
+    /// means something answered; otherwise the error from [`Address::Alt`]
+    /// is.
+    ///
+    /// [`Address::Default`]: data_bus::i2c::Address::Default
+    /// [`Address::Alt`]: data_bus::i2c::Address::Alt
+    #[allow(clippy::type_complexity)]
+    pub fn probe_i2c_any(
+        bus: I2C,
+    ) -> Result<(Self, data_bus::i2c::Address), (I2C, Error<data_bus::i2c::I2CError<I2C>>)> {
+        use data_bus::i2c::Address;
+
+        let (bus, default_error) = match Self::probe_i2c(bus, Address::Default) {
+            Ok(device) => return Ok((device, Address::Default)),
+            Err(failure) => failure,
+        };
+        Self::probe_i2c(bus, Address::Alt)
+            .map(|device| (device, Address::Alt))
+            .map_err(|(bus, alt_error)| match (default_error, alt_error) {
+                (error @ Error::InvalidDeviceId(_), Error::Bus { .. }) => (bus, error),
+                (_, error) => (bus, error),
+            })
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI> ADXL345<data_bus::eh1::SPIDeviceBus<SPI>>
+where
+    SPI: embedded_hal_1::spi::SpiDevice,
+{
+    /// Talk to the device over an embedded-hal 1.0 [`SpiDevice`], which
+    /// takes care of chip select
+    ///
+    /// [`SpiDevice`]: embedded_hal_1::spi::SpiDevice
+    pub fn from_spi_device(bus: SPI) -> Self {
+        Self {
+            bus: data_bus::eh1::SPIDeviceBus::new(bus),
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<I2C> ADXL345<data_bus::eh1::I2CBus<I2C>>
+where
+    I2C: embedded_hal_1::i2c::I2c,
+{
+    /// Talk to the device over an embedded-hal 1.0 [`I2c`] bus
+    ///
+    /// [`I2c`]: embedded_hal_1::i2c::I2c
+    pub fn from_i2c_eh1(bus: I2C, address: data_bus::i2c::Address) -> Self {
+        Self {
+            bus: data_bus::eh1::I2CBus::new(bus, address),
+            data_format: 0,
+            calibration: None,
+            int_pin: NoPin,
+            _mode: PhantomData,
         }
     }
 }
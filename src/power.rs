@@ -0,0 +1,200 @@
+use nb::block;
+
+use crate::{
+    data_bus::DataBus,
+    error::Error,
+    register::{power_ctl, Readable, ReadingFrequencyHz, Writable, POWER_CTL},
+    ADXL345,
+};
+
+/// Power state of the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerState {
+    /// Not measuring, the state after power-on
+    Standby,
+    /// Measuring at the configured output data rate
+    Measuring,
+    /// Measuring at the given rate only, either on request or because of
+    /// inactivity with auto-sleep enabled
+    Sleeping(ReadingFrequencyHz),
+}
+
+/// Typestate of a driver whose device is in standby, where it can be
+/// configured
+pub struct Standby;
+
+/// Typestate of a driver whose device is measuring, where samples can be read
+pub struct Measuring;
+
+impl<BUS: DataBus, MODE, INT> ADXL345<BUS, MODE, INT> {
+    /// Report the current power state, from `POWER_CTL` and the `asleep` bit
+    /// of `ACT_TAP_STATUS`
+    pub fn power_state(&mut self) -> nb::Result<PowerState, Error<BUS::Error>> {
+        let ctl = self.power_ctl().read()?;
+        if !ctl.measure() {
+            return Ok(PowerState::Standby);
+        }
+
+        let asleep = ctl.sleep() || (ctl.auto_sleep() && self.act_tap_status().read()?.asleep());
+        if asleep {
+            Ok(PowerState::Sleeping(ctl.wakeup()))
+        } else {
+            Ok(PowerState::Measuring)
+        }
+    }
+
+    fn wake_from(&mut self, ctl: u8) -> nb::Result<(), Error<BUS::Error>> {
+        let ctl = self.update_power_ctl(ctl, |w| w.set_measure(false))?;
+        let ctl = self.update_power_ctl(ctl, |w| w.set_sleep(false))?;
+        self.update_power_ctl(ctl, |w| w.set_measure(true))?;
+        Ok(())
+    }
+
+    /// Write `ctl` changed by `f` to `POWER_CTL`, and return the value written
+    fn update_power_ctl<F>(&mut self, ctl: u8, f: F) -> nb::Result<u8, Error<BUS::Error>>
+    where
+        F: FnOnce(&mut power_ctl::Handle) -> &mut power_ctl::Handle,
+    {
+        let mut handle = POWER_CTL::fill(ctl);
+        f(&mut handle);
+        let ctl = POWER_CTL::into_raw(handle);
+
+        self.bus
+            .write::<POWER_CTL>(ctl)
+            .map_err(|e| e.map(Error::bus::<POWER_CTL>))?;
+        Ok(ctl)
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Standby, INT> {
+    /// Start measuring at the configured output data rate, waking the device
+    /// up if it was left asleep.
+    ///
+    /// `DATA_FORMAT` is read first, so that scaled reads and the interrupt
+    /// polarity follow the device even when it kept its settings across a
+    /// reset of the host.
+    ///
+    /// On failure the driver is handed back, still in standby.
+    #[allow(clippy::type_complexity)]
+    pub fn into_measuring(
+        mut self,
+    ) -> Result<ADXL345<BUS, Measuring, INT>, (Self, Error<BUS::Error>)> {
+        let result = block!(self.data_format().read())
+            .and_then(|_| {
+                block!(self
+                    .bus
+                    .read::<POWER_CTL>()
+                    .map_err(|e| e.map(Error::bus::<POWER_CTL>)))
+            })
+            .and_then(|ctl| {
+                if POWER_CTL::fill(ctl).sleep() {
+                    block!(self.wake_from(ctl))
+                } else {
+                    block!(self.update_power_ctl(ctl, |w| w.set_measure(true))).map(|_| ())
+                }
+            });
+
+        match result {
+            Ok(()) => Ok(self.into_mode()),
+            Err(error) => Err((self, error)),
+        }
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Measuring, INT> {
+    /// Stop measuring, keeping every other power setting.
+    ///
+    /// On failure the driver is handed back, still measuring.
+    #[allow(clippy::type_complexity)]
+    pub fn into_standby(mut self) -> Result<ADXL345<BUS, Standby, INT>, (Self, Error<BUS::Error>)> {
+        let result = block!(self
+            .bus
+            .read::<POWER_CTL>()
+            .map_err(|e| e.map(Error::bus::<POWER_CTL>)))
+        .and_then(|ctl| block!(self.update_power_ctl(ctl, |w| w.set_measure(false))));
+
+        match result {
+            Ok(_) => Ok(self.into_mode()),
+            Err(error) => Err((self, error)),
+        }
+    }
+
+    /// Put the device to sleep, sampling at `frequency` only. `DATA_READY`
+    /// is suppressed and the FIFO stops collecting samples while asleep
+    pub fn enter_sleep(
+        &mut self,
+        frequency: ReadingFrequencyHz,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let ctl = self
+            .bus
+            .read::<POWER_CTL>()
+            .map_err(|e| e.map(Error::bus::<POWER_CTL>))?;
+        self.update_power_ctl(ctl, |w| {
+            w.set_wakeup(frequency).set_sleep(true).set_measure(true)
+        })?;
+        Ok(())
+    }
+
+    /// Go back to measuring at the configured output data rate.
+    ///
+    /// As the datasheet recommends, the device goes through standby before
+    /// the sleep bit is cleared.
+    pub fn wake(&mut self) -> nb::Result<(), Error<BUS::Error>> {
+        let ctl = self
+            .bus
+            .read::<POWER_CTL>()
+            .map_err(|e| e.map(Error::bus::<POWER_CTL>))?;
+        self.wake_from(ctl)
+    }
+
+    /// Let the device switch to sleep on inactivity, sampling at `frequency`,
+    /// and back on activity. This links activity and inactivity detection, so
+    /// both must be set up beforehand.
+    ///
+    /// The device is left measuring.
+    pub fn enable_auto_sleep(
+        &mut self,
+        frequency: ReadingFrequencyHz,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let detection = self.act_inact_ctl().read()?;
+        if !(detection.act_x_enable() || detection.act_y_enable() || detection.act_z_enable()) {
+            return Err(Error::NotConfigured("activity").into());
+        }
+        if !(detection.inact_x_enable() || detection.inact_y_enable() || detection.inact_z_enable())
+        {
+            return Err(Error::NotConfigured("inactivity").into());
+        }
+
+        self.set_auto_sleep(true, frequency)
+    }
+
+    /// Stop switching to sleep on inactivity, and unlink activity and
+    /// inactivity detection.
+    ///
+    /// The device is left measuring.
+    pub fn disable_auto_sleep(&mut self) -> nb::Result<(), Error<BUS::Error>> {
+        let frequency = self.power_ctl().read()?.wakeup();
+        self.set_auto_sleep(false, frequency)
+    }
+
+    /// Changes to the link and auto-sleep bits are made in standby
+    fn set_auto_sleep(
+        &mut self,
+        enable: bool,
+        frequency: ReadingFrequencyHz,
+    ) -> nb::Result<(), Error<BUS::Error>> {
+        let ctl = self
+            .bus
+            .read::<POWER_CTL>()
+            .map_err(|e| e.map(Error::bus::<POWER_CTL>))?;
+        let ctl = self.update_power_ctl(ctl, |w| w.set_measure(false))?;
+        let ctl = self.update_power_ctl(ctl, |w| {
+            w.set_link(enable)
+                .set_auto_sleep(enable)
+                .set_sleep(false)
+                .set_wakeup(frequency)
+        })?;
+        self.update_power_ctl(ctl, |w| w.set_measure(true))?;
+        Ok(())
+    }
+}
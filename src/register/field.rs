@@ -2,6 +2,11 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::private::Sealed;
 
+/// Value of a register field.
+///
+/// `from_byte` only ever sees the bits of the field, and every enum field
+/// has a variant for each value its width allows, so decoding can't fail,
+/// whatever was read from the bus.
 pub(super) trait RegisterField: Sealed + Sized {
     fn from_byte(value: u8) -> Self;
 
@@ -71,7 +76,7 @@ impl RegisterField for u8 {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 pub enum FIFOMode {
     Bypass = 0,
     Fifo = 1,
@@ -83,7 +88,12 @@ impl Sealed for FIFOMode {}
 
 impl RegisterField for FIFOMode {
     fn from_byte(value: u8) -> Self {
-        FIFOMode::try_from(value).unwrap_or_else(|_| panic!())
+        match value & 0b11 {
+            0 => FIFOMode::Bypass,
+            1 => FIFOMode::Fifo,
+            2 => FIFOMode::Stream,
+            _ => FIFOMode::Trigger,
+        }
     }
 
     fn to_byte(self) -> u8 {
@@ -92,7 +102,7 @@ impl RegisterField for FIFOMode {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 pub enum GRange {
     Two = 0,
     Four = 1,
@@ -104,7 +114,12 @@ impl Sealed for GRange {}
 
 impl RegisterField for GRange {
     fn from_byte(value: u8) -> Self {
-        GRange::try_from(value).unwrap_or_else(|_| panic!())
+        match value & 0b11 {
+            0 => GRange::Two,
+            1 => GRange::Four,
+            2 => GRange::Eight,
+            _ => GRange::Sixteen,
+        }
     }
 
     fn to_byte(self) -> u8 {
@@ -113,7 +128,7 @@ impl RegisterField for GRange {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 pub enum ReadingFrequencyHz {
     Eight = 0,
     Four = 1,
@@ -125,7 +140,12 @@ impl Sealed for ReadingFrequencyHz {}
 
 impl RegisterField for ReadingFrequencyHz {
     fn from_byte(value: u8) -> Self {
-        ReadingFrequencyHz::try_from(value).unwrap_or_else(|_| panic!())
+        match value & 0b11 {
+            0 => ReadingFrequencyHz::Eight,
+            1 => ReadingFrequencyHz::Four,
+            2 => ReadingFrequencyHz::Two,
+            _ => ReadingFrequencyHz::One,
+        }
     }
 
     fn to_byte(self) -> u8 {
@@ -139,7 +159,7 @@ impl RegisterField for ReadingFrequencyHz {
 /// For example, `_0_78` represents a ***0.78 Hz*** rate, and `_400` represents
 /// a ***400 Hz*** rate.
 #[repr(u8)]
-#[derive(Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 pub enum OutputDataRateHz {
     _0_10 = 0,
     _0_20 = 1,
@@ -159,11 +179,36 @@ pub enum OutputDataRateHz {
     _3200 = 15,
 }
 
+impl OutputDataRateHz {
+    /// Time between two samples, in µs, rounded down
+    pub fn period_us(self) -> u32 {
+        // 3200 Hz is 312.5 µs, and every step down doubles the period
+        (10_000 << (15 - u8::from(self))) >> 5
+    }
+}
+
 impl Sealed for OutputDataRateHz {}
 
 impl RegisterField for OutputDataRateHz {
     fn from_byte(value: u8) -> Self {
-        OutputDataRateHz::try_from(value).unwrap_or_else(|_| panic!())
+        match value & 0b1111 {
+            0 => OutputDataRateHz::_0_10,
+            1 => OutputDataRateHz::_0_20,
+            2 => OutputDataRateHz::_0_39,
+            3 => OutputDataRateHz::_0_78,
+            4 => OutputDataRateHz::_1_56,
+            5 => OutputDataRateHz::_3_13,
+            6 => OutputDataRateHz::_6_25,
+            7 => OutputDataRateHz::_12_5,
+            8 => OutputDataRateHz::_25,
+            9 => OutputDataRateHz::_50,
+            10 => OutputDataRateHz::_100,
+            11 => OutputDataRateHz::_200,
+            12 => OutputDataRateHz::_400,
+            13 => OutputDataRateHz::_800,
+            14 => OutputDataRateHz::_1600,
+            _ => OutputDataRateHz::_3200,
+        }
     }
 
     fn to_byte(self) -> u8 {
@@ -174,7 +219,7 @@ impl RegisterField for OutputDataRateHz {
 #[cfg(test)]
 mod tests {
 
-    use super::RegisterField;
+    use super::{FIFOMode, GRange, OutputDataRateHz, ReadingFrequencyHz, RegisterField};
 
     #[test]
     fn bit_mangling() {
@@ -191,4 +236,23 @@ mod tests {
         assert_eq!(u8::set_inner_bits(input, 6, 3, 0b0000_1010), 0b0101_0100);
         assert_eq!(u8::set_inner_bits(input, 6, 3, 0b1101_1010), 0b0101_0100);
     }
+
+    #[test]
+    fn enum_fields_decode_every_value() {
+        for value in 0..4 {
+            assert_eq!(FIFOMode::from_byte(value).to_byte(), value);
+            assert_eq!(GRange::from_byte(value).to_byte(), value);
+            assert_eq!(ReadingFrequencyHz::from_byte(value).to_byte(), value);
+        }
+        for value in 0..16 {
+            assert_eq!(OutputDataRateHz::from_byte(value).to_byte(), value);
+        }
+
+        // Whatever the bus returns, only the bits of the field are decoded
+        assert_eq!(GRange::from_inner_bits(0xFF, 1, 0), GRange::Sixteen);
+        assert_eq!(
+            OutputDataRateHz::from_inner_bits(0b1110_1010, 3, 0),
+            OutputDataRateHz::_100
+        );
+    }
 }
@@ -6,7 +6,9 @@ use paste::paste;
 
 pub use field::*;
 
-use crate::{data_bus::DataBus, private, ADXL345};
+#[cfg(feature = "async")]
+use crate::data_bus::AsyncDataBus;
+use crate::{data_bus::DataBus, error::Error, interrupt::Interrupt, power::Standby, private};
 
 /// This trait is sealed to disallow external implementations.
 pub trait Register: private::Sealed {
@@ -26,25 +28,39 @@ pub trait Writable: Readable + private::Sealed {
     fn into_raw(w: Self::Handle) -> u8;
 }
 
-pub struct RegisterHandle<'s, R, BUS>(&'s mut ADXL345<BUS>, PhantomData<R>);
+/// Access to a single register. Registers can be read in any mode, but only
+/// written while the driver is in standby.
+pub struct RegisterHandle<'s, R, BUS, MODE> {
+    bus: &'s mut BUS,
+    /// Driver copy of `DATA_FORMAT`
+    data_format: &'s mut u8,
+    _register: PhantomData<(R, MODE)>,
+}
 
-impl<'s, R, BUS> RegisterHandle<'s, R, BUS>
+impl<'s, R, BUS, MODE> RegisterHandle<'s, R, BUS, MODE>
 where
     BUS: DataBus,
     R: Register,
 {
     /// Read the specified register
-    pub fn read(&mut self) -> nb::Result<R::Handle, BUS::Error>
+    pub fn read(&mut self) -> nb::Result<R::Handle, Error<BUS::Error>>
     where
         R: Readable,
     {
-        let reg = self.0.bus.read::<R>()?;
+        let reg = self.bus.read::<R>().map_err(|e| e.map(Error::bus::<R>))?;
+        self.track(reg);
 
         Ok(R::fill(reg))
     }
+}
 
+impl<'s, R, BUS> RegisterHandle<'s, R, BUS, Standby>
+where
+    BUS: DataBus,
+    R: Register,
+{
     /// Write to the specified register
-    pub fn write<F>(&mut self, f: F) -> nb::Result<(), BUS::Error>
+    pub fn write<F>(&mut self, f: F) -> nb::Result<(), Error<BUS::Error>>
     where
         R: Writable,
         R::Handle: Default,
@@ -54,13 +70,17 @@ where
 
         f(&mut reg);
 
-        self.0.bus.write::<R>(R::into_raw(reg))?;
+        let raw = R::into_raw(reg);
+        self.bus
+            .write::<R>(raw)
+            .map_err(|e| e.map(Error::bus::<R>))?;
+        self.track(raw);
 
         Ok(())
     }
 
     /// Modify the specified register
-    pub fn modify<F>(&mut self, f: F) -> nb::Result<(), BUS::Error>
+    pub fn modify<F>(&mut self, f: F) -> nb::Result<(), Error<BUS::Error>>
     where
         R: Writable,
         F: FnOnce(&mut R::Handle) -> &mut R::Handle,
@@ -69,18 +89,126 @@ where
 
         f(&mut reg);
 
-        self.0.bus.write::<R>(R::into_raw(reg))?;
+        let raw = R::into_raw(reg);
+        self.bus
+            .write::<R>(raw)
+            .map_err(|e| e.map(Error::bus::<R>))?;
+        self.track(raw);
+
+        Ok(())
+    }
+}
+
+impl<'s, R: Register, BUS, MODE> RegisterHandle<'s, R, BUS, MODE> {
+    fn new(bus: &'s mut BUS, data_format: &'s mut u8) -> Self {
+        Self {
+            bus,
+            data_format,
+            _register: PhantomData,
+        }
+    }
+
+    /// Keep the local copy of the registers the driver depends on up to date
+    fn track(&mut self, raw: u8) {
+        if R::ADDRESS == DATA_FORMAT::ADDRESS {
+            *self.data_format = raw;
+        }
+    }
+}
+
+/// Async counterpart of [`RegisterHandle`]
+#[cfg(feature = "async")]
+pub struct AsyncRegisterHandle<'s, R, BUS, MODE> {
+    bus: &'s mut BUS,
+    /// Driver copy of `DATA_FORMAT`
+    data_format: &'s mut u8,
+    _register: PhantomData<(R, MODE)>,
+}
+
+#[cfg(feature = "async")]
+impl<'s, R, BUS, MODE> AsyncRegisterHandle<'s, R, BUS, MODE>
+where
+    BUS: AsyncDataBus,
+    R: Register,
+{
+    /// Read the specified register
+    pub async fn read(&mut self) -> Result<R::Handle, Error<BUS::Error>>
+    where
+        R: Readable,
+    {
+        let reg = self.bus.read::<R>().await.map_err(Error::bus::<R>)?;
+        self.track(reg);
+
+        Ok(R::fill(reg))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'s, R, BUS> AsyncRegisterHandle<'s, R, BUS, Standby>
+where
+    BUS: AsyncDataBus,
+    R: Register,
+{
+    /// Write to the specified register
+    pub async fn write<F>(&mut self, f: F) -> Result<(), Error<BUS::Error>>
+    where
+        R: Writable,
+        R::Handle: Default,
+        F: FnOnce(&mut R::Handle) -> &mut R::Handle,
+    {
+        let mut reg = R::Handle::default();
+
+        f(&mut reg);
+
+        let raw = R::into_raw(reg);
+        self.bus.write::<R>(raw).await.map_err(Error::bus::<R>)?;
+        self.track(raw);
+
+        Ok(())
+    }
+
+    /// Modify the specified register
+    pub async fn modify<F>(&mut self, f: F) -> Result<(), Error<BUS::Error>>
+    where
+        R: Writable,
+        F: FnOnce(&mut R::Handle) -> &mut R::Handle,
+    {
+        let mut reg = self.read().await?;
+
+        f(&mut reg);
+
+        let raw = R::into_raw(reg);
+        self.bus.write::<R>(raw).await.map_err(Error::bus::<R>)?;
+        self.track(raw);
 
         Ok(())
     }
 }
 
+#[cfg(feature = "async")]
+impl<'s, R: Register, BUS, MODE> AsyncRegisterHandle<'s, R, BUS, MODE> {
+    fn new(bus: &'s mut BUS, data_format: &'s mut u8) -> Self {
+        Self {
+            bus,
+            data_format,
+            _register: PhantomData,
+        }
+    }
+
+    /// Keep the local copy of the registers the driver depends on up to date
+    fn track(&mut self, raw: u8) {
+        if R::ADDRESS == DATA_FORMAT::ADDRESS {
+            *self.data_format = raw;
+        }
+    }
+}
+
 macro_rules! sys_register {
     (
         $(
             $addr:expr,
             $rw:tt,
-            $name:ident {
+            $vis:vis $name:ident {
             #[$doc:meta]
             $(
                 $field:ident,
@@ -127,11 +255,24 @@ macro_rules! sys_register {
                 impl_rw!($rw, $name);
             )*
 
-            impl<BUS> crate::ADXL345<BUS> {
+            impl<BUS, MODE, INT> crate::ADXL345<BUS, MODE, INT> {
                 $(
                     #[$doc]
-                    pub fn [<$name:lower>](&mut self) -> RegisterHandle<$name, BUS> {
-                        RegisterHandle(self, PhantomData)
+                    $vis fn [<$name:lower>](&mut self) -> RegisterHandle<'_, $name, BUS, MODE> {
+                        RegisterHandle::new(&mut self.bus, &mut self.data_format)
+                    }
+                )*
+            }
+
+            #[cfg(feature = "async")]
+            impl<BUS, MODE, INT> crate::asynch::ADXL345Async<BUS, MODE, INT> {
+                $(
+                    #[$doc]
+                    // Crate-private registers may only be needed by the
+                    // blocking driver
+                    #[allow(dead_code)]
+                    $vis fn [<$name:lower>](&mut self) -> AsyncRegisterHandle<'_, $name, BUS, MODE> {
+                        AsyncRegisterHandle::new(&mut self.bus, &mut self.data_format)
                     }
                 )*
             }
@@ -278,41 +419,41 @@ macro_rules! impl_rw {
 }
 
 sys_register! {
-    0x00, RO, DEVID { ///Device ID
+    0x00, RO, pub DEVID { ///Device ID
         value, 7, 0, u8; ///Device ID
     }
     // 0X01 to 0X1C     Reserved; do not access
-    0x1D, RW, THRESH_TAP { ///Tap threshold
+    0x1D, RW, pub THRESH_TAP { ///Tap threshold
         value, 7, 0, u8; ///Tap threshold
     }
-    0x1E, RW, OFSX { ///X-axis offset
+    0x1E, RW, pub OFSX { ///X-axis offset
         value, 7, 0, i8; ///X-axis offset
     }
-    0x1F, RW, OFSY { /// Y-axis offset
+    0x1F, RW, pub OFSY { /// Y-axis offset
         value, 7, 0, i8; ///Y-axis offset
     }
-    0x20, RW, OFSZ { ///Z-axis offset
+    0x20, RW, pub OFSZ { ///Z-axis offset
         value, 7, 0, i8; ///Z-axis offset
     }
-    0x21, RW, DUR { ///Tap duration
+    0x21, RW, pub DUR { ///Tap duration
         value, 7, 0, u8; ///Tap duration
     }
-    0x22, RW, Latent { ///Tap latency
+    0x22, RW, pub Latent { ///Tap latency
         value, 7, 0, u8; ///Tap latency
     }
-    0x23, RW, Window { ///Tap window
+    0x23, RW, pub Window { ///Tap window
         value, 7, 0, u8; ///Tap window
     }
-    0x24, RW, THRESH_ACT { ///Activity threshold
+    0x24, RW, pub THRESH_ACT { ///Activity threshold
         value, 7, 0, u8; ///Activity threshold
     }
-    0x25, RW, THRESH_INACT { ///Inactivity threshold
+    0x25, RW, pub THRESH_INACT { ///Inactivity threshold
         value, 7, 0, u8; ///Inactivity threshold
     }
-    0x26, RW, TIME_INACT { ///Inactivity time
+    0x26, RW, pub TIME_INACT { ///Inactivity time
         value, 7, 0, u8; ///Inactivity time
     }
-    0x27, RW, ACT_INACT_CTL { ///Axis enable control for activity and inactivity detection
+    0x27, RW, pub ACT_INACT_CTL { ///Axis enable control for activity and inactivity detection
         act_ac_dc, 7, 7, bool; ///ACT ac/dc
         act_x_enable, 6, 6, bool; ///ACT_X enable
         act_y_enable, 5, 5, bool; ///ACT_Y enable
@@ -322,13 +463,13 @@ sys_register! {
         inact_y_enable, 1, 1, bool; ///INACT_Y enable
         inact_z_enable, 0, 0, bool; ///INACT_Z enable
     }
-    0x28, RW, THRESH_FF { ///Free-fall threshold
+    0x28, RW, pub THRESH_FF { ///Free-fall threshold
         value, 7, 0, u8; ///Free-fall threshold
     }
-    0x29, RW, TIME_FF { ///Free-fall time
+    0x29, RW, pub TIME_FF { ///Free-fall time
         value, 7, 0, u8; ///Free-fall time
     }
-    0x2A, RW, TAP_AXES { ///Axis control for single tap/double tap
+    0x2A, RW, pub TAP_AXES { ///Axis control for single tap/double tap
         // 0
         // 0
         // 0
@@ -338,7 +479,7 @@ sys_register! {
         tap_y_enable, 1, 1, bool; ///TAP_Y enable
         tap_z_enable, 0, 0, bool; ///TAP_Z enable
     }
-    0x2B, RO, ACT_TAP_STATUS { ///Source of single tap/double tap
+    0x2B, RO, pub ACT_TAP_STATUS { ///Source of single tap/double tap
         // 0
         act_x_source, 6, 6, bool; ///ACT_X source
         act_y_source, 5, 5, bool; ///ACT_Y source
@@ -349,14 +490,15 @@ sys_register! {
         tap_z_source, 0, 0, bool; ///TAP_Z source
 
     }
-    0x2C, RW, BW_RATE { ///Data rate and power mode control
+    0x2C, RW, pub BW_RATE { ///Data rate and power mode control
         // 0
         // 0
         // 0
         low_power, 4, 4, bool; ///LOW_POWER,
         rate, 3, 0, OutputDataRateHz; ///Rate
     }
-    0x2D, RW, POWER_CTL { ///Power-saving features control
+    // Measurement and sleep are switched through the `Standby`/`Measuring` typestate
+    0x2D, RW, pub(crate) POWER_CTL { ///Power-saving features control
         // 0
         // 0
         link, 5, 5, bool; ///Link
@@ -365,7 +507,7 @@ sys_register! {
         sleep, 2, 2, bool; ///Sleep
         wakeup, 1, 0, ReadingFrequencyHz; ///Wakeup
     }
-    0x2E, RW, INT_ENABLE { ///Interrupt enable control
+    0x2E, RW, pub INT_ENABLE { ///Interrupt enable control
         data_ready, 7, 7, bool; ///DATA_READY
         single_tap, 6, 6, bool; ///SINGLE_TAP
         double_tap, 5, 5, bool; ///DOUBLE_TAP
@@ -375,7 +517,7 @@ sys_register! {
         watermark, 1, 1, bool; ///Watermark
         overrun, 0, 0, bool; ///Overrun
     }
-    0x2F, RW, INT_MAP { ///Interrupt mapping control
+    0x2F, RW, pub INT_MAP { ///Interrupt mapping control
         data_ready, 7, 7, bool; ///DATA_READY
         single_tap, 6, 6, bool; ///SINGLE_TAP
         double_tap, 5, 5, bool; ///DOUBLE_TAP
@@ -385,7 +527,7 @@ sys_register! {
         watermark, 1, 1, bool; ///Watermark
         overrun, 0, 0, bool; ///Overrun
     }
-    0x30, RO, INT_SOURCE { ///Source of interrupts
+    0x30, RO, pub INT_SOURCE { ///Source of interrupts
         data_ready, 7, 7, bool; ///DATA_READY
         single_tap, 6, 6, bool; ///SINGLE_TAP
         double_tap, 5, 5, bool; ///DOUBLE_TAP
@@ -395,7 +537,7 @@ sys_register! {
         watermark, 1, 1, bool; ///Watermark
         overrun, 0, 0, bool; ///Overrun
     }
-    0x31, RW, DATA_FORMAT { ///Data format control
+    0x31, RW, pub DATA_FORMAT { ///Data format control
         self_test, 7, 7, bool; ///SELF_TEST
         spi, 6, 6, bool; ///SPI
         int_invert, 5, 5, bool; ///INT_INVERT
@@ -404,32 +546,46 @@ sys_register! {
         justify, 2, 2, bool; ///Justify
         range, 1, 0, GRange; ///Range
     }
-    0x32, RO, DATAX0 { ///X-Axis Data 0
+    0x32, RO, pub DATAX0 { ///X-Axis Data 0
         value, 7, 0, u8; ///X-Axis Data 0
     }
-    0x33, RO, DATAX1 { ///X-Axis Data 1
+    0x33, RO, pub DATAX1 { ///X-Axis Data 1
         value, 7, 0, u8; ///X-Axis Data 1
     }
-    0x34, RO, DATAY0 { ///Y-Axis Data 0
+    0x34, RO, pub DATAY0 { ///Y-Axis Data 0
         value, 7, 0, u8; ///Y-Axis Data 0
     }
-    0x35, RO, DATAY1 { ///Y-Axis Data 1
+    0x35, RO, pub DATAY1 { ///Y-Axis Data 1
         value, 7, 0, u8; ///Y-Axis Data 1
     }
-    0x36, RO, DATAZ0 { ///Z-Axis Data 0
+    0x36, RO, pub DATAZ0 { ///Z-Axis Data 0
         value, 7, 0, u8; ///Z-Axis Data 0
     }
-    0x37, RO, DATAZ1 { ///Z-Axis Data 1
+    0x37, RO, pub DATAZ1 { ///Z-Axis Data 1
         value, 7, 0, u8; ///Z-Axis Data 1
     }
-    0x38, RW, FIFO_CTL { ///FIFO control
+    0x38, RW, pub FIFO_CTL { ///FIFO control
         fifo_mode, 7, 6, FIFOMode; ///FIFO_MODE
         trigger, 5, 5, bool; ///Trigger
         samples, 4, 0, u8; ///Samples
     }
-    0x39, RO, FIFO_STATUS { ///FIFO status
+    0x39, RO, pub FIFO_STATUS { ///FIFO status
         fifo_trig, 7, 7, bool; ///FIFO_TRIG
         // 0
         entries, 5, 0, u8; ///Entries
     }
 }
+
+macro_rules! impl_interrupt_conversions {
+    ($($module:ident),*) => {
+        $(
+            impl From<$module::Handle> for Interrupt {
+                fn from(handle: $module::Handle) -> Self {
+                    Interrupt::from_bits_truncate(handle.0)
+                }
+            }
+        )*
+    };
+}
+
+impl_interrupt_conversions!(int_enable, int_map, int_source);
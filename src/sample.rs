@@ -0,0 +1,304 @@
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::{
+    calibration::{round, Calibration},
+    data_bus::DataBus,
+    error::Error,
+    power::Measuring,
+    register::{data_format, Readable, DATAX0, DATA_FORMAT},
+    ADXL345,
+};
+
+/// Standard gravity, in m/s²
+pub const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// A value for each of the three axes of the device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sample<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+/// Acceleration sample as stored in the `DATAX0`..`DATAZ1` registers
+pub type RawSample = Sample<i16>;
+
+impl RawSample {
+    /// Decode the six data registers, in address order
+    pub fn from_le_bytes(bytes: [u8; 6]) -> Self {
+        Self {
+            x: i16::from_le_bytes([bytes[0], bytes[1]]),
+            y: i16::from_le_bytes([bytes[2], bytes[3]]),
+            z: i16::from_le_bytes([bytes[4], bytes[5]]),
+        }
+    }
+}
+
+/// Conversion from raw counts to acceleration, as selected by the `range`,
+/// `full_res` and `justify` fields of `DATA_FORMAT`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scale {
+    /// Right shift needed to undo left justification
+    justify_shift: u8,
+    /// Scale factor is `2^resolution_shift / 256` g/LSB
+    resolution_shift: u8,
+}
+
+impl Scale {
+    pub fn from_data_format(format: &data_format::Handle) -> Self {
+        let range = u8::from(format.range());
+        // In full resolution mode the sensitivity stays at 256 LSB/g and the
+        // word grows by one bit per range step instead
+        let (bits, resolution_shift) = if format.full_res() {
+            (10 + range, 0)
+        } else {
+            (10, range)
+        };
+        let justify_shift = if format.justify() { 16 - bits } else { 0 };
+
+        Self {
+            justify_shift,
+            resolution_shift,
+        }
+    }
+
+    /// Sensitivity in LSB/g
+    pub fn lsb_per_g(self) -> u16 {
+        256 >> self.resolution_shift
+    }
+
+    /// Right-justified counts
+    pub fn counts(self, raw: RawSample) -> RawSample {
+        Sample {
+            x: raw.x >> self.justify_shift,
+            y: raw.y >> self.justify_shift,
+            z: raw.z >> self.justify_shift,
+        }
+    }
+
+    /// Acceleration in mg
+    pub fn to_mg(self, raw: RawSample) -> Sample<i32> {
+        let mg = |value: i16| ((i32::from(value) * 1000) << self.resolution_shift) / 256;
+        let counts = self.counts(raw);
+        Sample {
+            x: mg(counts.x),
+            y: mg(counts.y),
+            z: mg(counts.z),
+        }
+    }
+
+    /// Acceleration in g
+    pub fn to_g(self, raw: RawSample) -> Sample<f32> {
+        let lsb_per_g = f32::from(self.lsb_per_g());
+        let counts = self.counts(raw);
+        Sample {
+            x: f32::from(counts.x) / lsb_per_g,
+            y: f32::from(counts.y) / lsb_per_g,
+            z: f32::from(counts.z) / lsb_per_g,
+        }
+    }
+
+    /// Acceleration in m/s²
+    pub fn to_mps2(self, raw: RawSample) -> Sample<f32> {
+        g_to_mps2(self.to_g(raw))
+    }
+}
+
+pub(crate) fn g_to_mps2(g: Sample<f32>) -> Sample<f32> {
+    Sample {
+        x: g.x * STANDARD_GRAVITY,
+        y: g.y * STANDARD_GRAVITY,
+        z: g.z * STANDARD_GRAVITY,
+    }
+}
+
+/// Acceleration in mg, with `calibration` applied if any
+pub(crate) fn calibrated_mg(
+    scale: Scale,
+    calibration: Option<Calibration>,
+    raw: RawSample,
+) -> Sample<i32> {
+    match calibration {
+        Some(calibration) => {
+            let g = calibration.apply(scale.to_g(raw));
+            Sample {
+                x: round(g.x * 1000.0),
+                y: round(g.y * 1000.0),
+                z: round(g.z * 1000.0),
+            }
+        }
+        None => scale.to_mg(raw),
+    }
+}
+
+/// Acceleration in g, with `calibration` applied if any
+pub(crate) fn calibrated_g(
+    scale: Scale,
+    calibration: Option<Calibration>,
+    raw: RawSample,
+) -> Sample<f32> {
+    let g = scale.to_g(raw);
+    match calibration {
+        Some(calibration) => calibration.apply(g),
+        None => g,
+    }
+}
+
+impl<BUS, MODE, INT> ADXL345<BUS, MODE, INT> {
+    /// Scale matching the last value written to or read from `DATA_FORMAT`
+    pub fn scale(&self) -> Scale {
+        Scale::from_data_format(&DATA_FORMAT::fill(self.data_format))
+    }
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Measuring, INT> {
+    /// Read the three axes in a single multi-byte transaction, so that all of
+    /// them belong to the same measurement
+    pub fn read_raw(&mut self) -> nb::Result<RawSample, Error<BUS::Error>> {
+        let mut buffer = [0u8; 6];
+        self.bus
+            .read_all::<DATAX0>(&mut buffer)
+            .map_err(|e| e.map(Error::bus::<DATAX0>))?;
+        Ok(RawSample::from_le_bytes(buffer))
+    }
+
+    /// Same as [`ADXL345::read_raw`]
+    pub fn read_acceleration(&mut self) -> nb::Result<RawSample, Error<BUS::Error>> {
+        self.read_raw()
+    }
+
+    /// Read the three axes in mg, calibrated if a calibration is set
+    pub fn read_mg(&mut self) -> nb::Result<Sample<i32>, Error<BUS::Error>> {
+        let raw = self.read_raw()?;
+        Ok(calibrated_mg(self.scale(), self.calibration, raw))
+    }
+
+    /// Read the three axes in g, calibrated if a calibration is set
+    pub fn read_g(&mut self) -> nb::Result<Sample<f32>, Error<BUS::Error>> {
+        let raw = self.read_raw()?;
+        Ok(calibrated_g(self.scale(), self.calibration, raw))
+    }
+
+    /// Read the three axes in m/s², calibrated if a calibration is set
+    pub fn read_mps2(&mut self) -> nb::Result<Sample<f32>, Error<BUS::Error>> {
+        let g = self.read_g()?;
+        Ok(g_to_mps2(g))
+    }
+
+    /// Average `samples` uncalibrated readings in g, one per `period_us`,
+    /// after waiting `settle_us` for the output to settle
+    pub(crate) fn average_g<D: DelayUs<u32>>(
+        &mut self,
+        samples: u8,
+        period_us: u32,
+        settle_us: u32,
+        delay: &mut D,
+    ) -> nb::Result<Sample<f32>, Error<BUS::Error>> {
+        delay.delay_us(settle_us);
+
+        let mut sum = Sample::<f32>::default();
+        for _ in 0..samples {
+            let raw = self.read_raw()?;
+            let g = self.scale().to_g(raw);
+            sum.x += g.x;
+            sum.y += g.y;
+            sum.z += g.z;
+            delay.delay_us(period_us);
+        }
+
+        let count = f32::from(samples);
+        Ok(Sample {
+            x: sum.x / count,
+            y: sum.y / count,
+            z: sum.z / count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RawSample, Sample, Scale};
+    use crate::register::{data_format, GRange, Readable, DATA_FORMAT};
+
+    fn scale(f: impl FnOnce(&mut data_format::Handle) -> &mut data_format::Handle) -> Scale {
+        let mut format = DATA_FORMAT::fill(0);
+        f(&mut format);
+        Scale::from_data_format(&format)
+    }
+
+    #[test]
+    fn scaling() {
+        let one_g = RawSample {
+            x: 256,
+            y: -256,
+            z: 0,
+        };
+
+        let two = scale(|w| w.set_range(GRange::Two));
+        assert_eq!(
+            two.to_mg(one_g),
+            Sample {
+                x: 1000,
+                y: -1000,
+                z: 0
+            }
+        );
+
+        let sixteen = scale(|w| w.set_range(GRange::Sixteen));
+        assert_eq!(
+            sixteen.to_mg(one_g),
+            Sample {
+                x: 8000,
+                y: -8000,
+                z: 0
+            }
+        );
+
+        let full_res = scale(|w| w.set_range(GRange::Sixteen).set_full_res(true));
+        assert_eq!(
+            full_res.to_mg(one_g),
+            Sample {
+                x: 1000,
+                y: -1000,
+                z: 0
+            }
+        );
+    }
+
+    #[test]
+    fn justify() {
+        let ten_bit = scale(|w| w.set_range(GRange::Four).set_justify(true));
+        let raw = RawSample {
+            x: 128 << 6,
+            y: -128 << 6,
+            z: 0,
+        };
+        assert_eq!(
+            ten_bit.to_mg(raw),
+            Sample {
+                x: 1000,
+                y: -1000,
+                z: 0
+            }
+        );
+
+        let full_res = scale(|w| {
+            w.set_range(GRange::Eight)
+                .set_full_res(true)
+                .set_justify(true)
+        });
+        let raw = RawSample {
+            x: 256 << 4,
+            y: -256 << 4,
+            z: 0,
+        };
+        assert_eq!(
+            full_res.to_g(raw),
+            Sample {
+                x: 1.0,
+                y: -1.0,
+                z: 0.0
+            }
+        );
+    }
+}
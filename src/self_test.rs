@@ -0,0 +1,223 @@
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::{
+    config::Axes,
+    data_bus::DataBus,
+    error::Error,
+    power::Measuring,
+    register::{GRange, OutputDataRateHz, Readable, Writable, DATA_FORMAT},
+    sample::Sample,
+    ADXL345,
+};
+
+/// Lowest self-test output change at 2.5 V, in LSB at 256 LSB/g
+const MIN_CHANGE: Sample<i16> = Sample {
+    x: 50,
+    y: -540,
+    z: 75,
+};
+
+/// Highest self-test output change at 2.5 V, in LSB at 256 LSB/g
+const MAX_CHANGE: Sample<i16> = Sample {
+    x: 540,
+    y: -50,
+    z: 875,
+};
+
+/// Self-test output change relative to 2.5 V, as `(mV, X and Y, Z)`
+const SUPPLY_SCALE: [(u16, f32, f32); 4] = [
+    (2000, 0.64, 0.8),
+    (2500, 1.0, 1.0),
+    (3300, 1.77, 1.47),
+    (3600, 2.11, 1.69),
+];
+
+/// Output periods to wait for the output to settle after toggling self-test
+const SETTLE_PERIODS: u32 = 4;
+
+/// Outcome of [`ADXL345::self_test`], in g
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelfTestReport {
+    /// Average output with self-test off
+    pub off: Sample<f32>,
+    /// Average output with self-test on
+    pub on: Sample<f32>,
+    /// Lowest change allowed at the given supply voltage
+    pub min: Sample<f32>,
+    /// Highest change allowed at the given supply voltage
+    pub max: Sample<f32>,
+}
+
+impl SelfTestReport {
+    /// Output change caused by the self-test force
+    pub fn change(&self) -> Sample<f32> {
+        Sample {
+            x: self.on.x - self.off.x,
+            y: self.on.y - self.off.y,
+            z: self.on.z - self.off.z,
+        }
+    }
+
+    /// Axes whose change is outside the datasheet limits
+    pub fn failed_axes(&self) -> Axes {
+        let change = self.change();
+        let within = |value: f32, min: f32, max: f32| (min..=max).contains(&value);
+
+        !Axes::from_enables(
+            within(change.x, self.min.x, self.max.x),
+            within(change.y, self.min.y, self.max.y),
+            within(change.z, self.min.z, self.max.z),
+        )
+    }
+
+    /// Whether every axis is within the datasheet limits
+    pub fn passed(&self) -> bool {
+        self.failed_axes().is_empty()
+    }
+
+    /// Hand the report back if every axis passed, or fail with the axes
+    /// that didn't
+    pub fn check<E>(self) -> Result<Self, Error<E>> {
+        match self.failed_axes() {
+            axes if axes.is_empty() => Ok(self),
+            axes => Err(Error::SelfTestFailed(axes)),
+        }
+    }
+}
+
+/// Scale factors for X and Y, and for Z, at `supply_mv`
+fn supply_scale<E>(supply_mv: u16) -> Result<(f32, f32), Error<E>> {
+    SUPPLY_SCALE
+        .windows(2)
+        .find(|pair| (pair[0].0..=pair[1].0).contains(&supply_mv))
+        .map(|pair| {
+            let (low, high) = (pair[0], pair[1]);
+            let t = f32::from(supply_mv - low.0) / f32::from(high.0 - low.0);
+            (low.1 + (high.1 - low.1) * t, low.2 + (high.2 - low.2) * t)
+        })
+        .ok_or(Error::OutOfRange {
+            field: "supply_mv",
+            value: supply_mv.into(),
+        })
+}
+
+impl<BUS: DataBus, INT> ADXL345<BUS, Measuring, INT> {
+    /// Run the built-in self-test, averaging `samples` readings with the
+    /// self-test force off and then on.
+    ///
+    /// The datasheet limits only hold in normal power mode at 100 to 800 Hz
+    /// or at 3200 Hz, so other data rates are rejected. `supply_mv` is the
+    /// supply voltage, from 2000 to 3600 mV, which the limits scale with.
+    ///
+    /// `DATA_FORMAT` is switched to full resolution at ±16 g for the test,
+    /// which leaves the most headroom for the change to show up unclipped,
+    /// and written back as it was afterwards, even if the test fails.
+    pub fn self_test<D: DelayUs<u32>>(
+        &mut self,
+        supply_mv: u16,
+        samples: u8,
+        delay: &mut D,
+    ) -> nb::Result<SelfTestReport, Error<BUS::Error>> {
+        if samples == 0 {
+            return Err(Error::OutOfRange {
+                field: "samples",
+                value: 0,
+            }
+            .into());
+        }
+        let (xy_scale, z_scale) = supply_scale(supply_mv)?;
+
+        let bw_rate = self.bw_rate().read()?;
+        let rate = u8::from(bw_rate.rate());
+        let valid_rate = (u8::from(OutputDataRateHz::_100)..=u8::from(OutputDataRateHz::_800))
+            .contains(&rate)
+            || bw_rate.rate() == OutputDataRateHz::_3200;
+        if bw_rate.low_power() || !valid_rate {
+            return Err(Error::OutOfRange {
+                field: "data_rate",
+                value: rate.into(),
+            }
+            .into());
+        }
+        let period_us = bw_rate.rate().period_us();
+
+        let original = DATA_FORMAT::into_raw(self.data_format().read()?);
+        let outputs = self.self_test_outputs(original, samples, period_us, delay);
+        self.write_data_format(original)?;
+        let (off, on) = outputs?;
+
+        let limit = |change: Sample<i16>| Sample {
+            x: f32::from(change.x) / 256.0 * xy_scale,
+            y: f32::from(change.y) / 256.0 * xy_scale,
+            z: f32::from(change.z) / 256.0 * z_scale,
+        };
+
+        Ok(SelfTestReport {
+            off,
+            on,
+            min: limit(MIN_CHANGE),
+            max: limit(MAX_CHANGE),
+        })
+    }
+
+    /// Average outputs with the self-test force off and then on, at full
+    /// resolution and ±16 g but otherwise keeping `format`
+    #[allow(clippy::type_complexity)]
+    fn self_test_outputs<D: DelayUs<u32>>(
+        &mut self,
+        format: u8,
+        samples: u8,
+        period_us: u32,
+        delay: &mut D,
+    ) -> nb::Result<(Sample<f32>, Sample<f32>), Error<BUS::Error>> {
+        let settle_us = period_us * SETTLE_PERIODS;
+        let test_format = |self_test: bool| {
+            let mut format = DATA_FORMAT::fill(format);
+            format
+                .set_full_res(true)
+                .set_range(GRange::Sixteen)
+                .set_self_test(self_test);
+            DATA_FORMAT::into_raw(format)
+        };
+
+        self.write_data_format(test_format(false))?;
+        let off = self.average_g(samples, period_us, settle_us, delay)?;
+        self.write_data_format(test_format(true))?;
+        let on = self.average_g(samples, period_us, settle_us, delay)?;
+
+        Ok((off, on))
+    }
+
+    /// Write `DATA_FORMAT` as a whole, keeping the driver copy in step
+    fn write_data_format(&mut self, raw: u8) -> nb::Result<(), Error<BUS::Error>> {
+        self.bus
+            .write::<DATA_FORMAT>(raw)
+            .map_err(|e| e.map(Error::bus::<DATA_FORMAT>))?;
+        self.data_format = raw;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::supply_scale;
+    use crate::Error;
+
+    #[test]
+    fn supply_scaling() {
+        assert_eq!(supply_scale::<()>(2500).unwrap(), (1.0, 1.0));
+        assert_eq!(supply_scale::<()>(3600).unwrap(), (2.11, 1.69));
+
+        let (xy, z) = supply_scale::<()>(2900).unwrap();
+        assert!((xy - 1.385).abs() < 1e-4);
+        assert!((z - 1.235).abs() < 1e-4);
+
+        assert!(matches!(
+            supply_scale::<()>(1800),
+            Err(Error::OutOfRange {
+                field: "supply_mv",
+                value: 1800
+            })
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "async")]
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use adxl345_hal::data_bus::i2c as adxl_i2c;
+use adxl345_hal::register as reg;
+use adxl345_hal::register::Register;
+use adxl345_hal::{ADXL345Async, Interrupt, RawSample, Sample};
+
+use embedded_hal_mock_eh1::eh1::delay::NoopDelay;
+use embedded_hal_mock_eh1::eh1::digital as pin;
+use embedded_hal_mock_eh1::eh1::i2c;
+
+/// Run `future` to completion. Mocks never return pending
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn read_and_wait() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0000_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0010_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0010_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_0101]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0101]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1001]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x80, 0x00, 0x80, 0xFF, 0x00, 0x00],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_STATUS::ADDRESS], vec![1]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![1, 0, 2, 0, 3, 0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::ACT_TAP_STATUS::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b1000_0010]),
+    ];
+    let pin_expect = vec![
+        pin::Transaction::wait_for_state(pin::State::Low),
+        pin::Transaction::wait_for_state(pin::State::Low),
+    ];
+
+    let device = ADXL345Async::from_i2c(i2c::Mock::new(&expect), adxl_i2c::Address::Default);
+    let mut device = device.with_int_pin(pin::Mock::new(&pin_expect));
+
+    block_on(async {
+        device
+            .data_format()
+            .modify(|w| w.set_int_invert(true))
+            .await
+            .unwrap();
+    });
+    let mut device = block_on(device.into_measuring())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    block_on(async {
+        device.wait_for_data_ready().await.unwrap();
+        assert_eq!(
+            device.read_mg().await.unwrap(),
+            Sample {
+                x: 1000,
+                y: -1000,
+                z: 0
+            }
+        );
+
+        let mut samples = [RawSample::default(); 4];
+        assert_eq!(
+            device
+                .read_fifo(&mut samples, &mut NoopDelay)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(samples[0], RawSample { x: 1, y: 2, z: 3 });
+
+        assert_eq!(
+            device.wait_for_event().await.unwrap().interrupts,
+            Interrupt::DATA_READY | Interrupt::WATERMARK
+        );
+    });
+
+    let (device, mut pin) = device.release_int_pin();
+    pin.done();
+    device.destroy().done();
+}
+
+#[test]
+fn wake_keeps_power_settings() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        // Linked, auto-sleeping and left asleep at 2 Hz
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0011_0110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0011_0110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0011_0010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0011_1010]),
+    ];
+
+    let device = ADXL345Async::from_i2c(i2c::Mock::new(&expect), adxl_i2c::Address::Default);
+    let device = block_on(device.into_measuring())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    device.destroy().done();
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "eh1")]
+
+use adxl345_hal::data_bus::i2c as adxl_i2c;
+use adxl345_hal::register as reg;
+use adxl345_hal::register::Register;
+use adxl345_hal::{Sample, ADXL345};
+
+use embedded_hal_mock_eh1::eh1::i2c;
+use embedded_hal_mock_eh1::eh1::spi;
+
+/// `THRESH_TAP` to `INT_MAP`, as read back by `read_config`
+fn detection_registers() -> Vec<u8> {
+    let mut registers = vec![0; 19];
+    registers[0] = 48;
+    registers
+}
+
+#[test]
+fn spi_device_operations() {
+    let single_read = |address: u8, value: u8| {
+        [
+            spi::Transaction::transaction_start(),
+            spi::Transaction::transfer_in_place(vec![0x80 | address, 0], vec![0, value]),
+            spi::Transaction::transaction_end(),
+        ]
+    };
+    // Bursts are a command write and a data read or write in one transaction
+    let mut expect = vec![
+        spi::Transaction::transaction_start(),
+        spi::Transaction::write_vec(vec![0xC0 | reg::THRESH_TAP::ADDRESS]),
+        spi::Transaction::read_vec(detection_registers()),
+        spi::Transaction::transaction_end(),
+    ];
+    expect.extend(single_read(reg::DATA_FORMAT::ADDRESS, 0b0000_0011));
+    expect.extend(single_read(reg::FIFO_CTL::ADDRESS, 0));
+    expect.extend([
+        spi::Transaction::transaction_start(),
+        spi::Transaction::write_vec(vec![0x40 | reg::OFSX::ADDRESS]),
+        spi::Transaction::write_vec(vec![1, 2, 3]),
+        spi::Transaction::transaction_end(),
+        spi::Transaction::transaction_start(),
+        spi::Transaction::write_vec(vec![reg::DATA_FORMAT::ADDRESS, 0b0000_1011]),
+        spi::Transaction::transaction_end(),
+    ]);
+
+    let mut device = ADXL345::from_spi_device(spi::Mock::new(&expect));
+
+    let config = device.read_config().unwrap();
+    assert_eq!(config.tap_threshold, 48);
+    assert_eq!(config.range, reg::GRange::Sixteen);
+    device.write_offsets(Sample { x: 1, y: 2, z: 3 }).unwrap();
+    device
+        .data_format()
+        .write(|w| w.set_full_res(true).set_range(reg::GRange::Sixteen))
+        .unwrap();
+
+    device.destroy().done();
+}
+
+#[test]
+fn i2c_operations() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::THRESH_TAP::ADDRESS],
+            detection_registers(),
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0000_0011]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_CTL::ADDRESS], vec![0]),
+        // Burst writes go out as one transaction, without a repeated start
+        i2c::Transaction::transaction_start(ADDRESS),
+        i2c::Transaction::write(ADDRESS, vec![reg::OFSX::ADDRESS]),
+        i2c::Transaction::write(ADDRESS, vec![1, 2, 3]),
+        i2c::Transaction::transaction_end(ADDRESS),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0000_1011]),
+    ];
+
+    let mut device = ADXL345::from_i2c_eh1(i2c::Mock::new(&expect), adxl_i2c::Address::Default);
+
+    let config = device.read_config().unwrap();
+    assert_eq!(config.tap_threshold, 48);
+    assert_eq!(config.range, reg::GRange::Sixteen);
+    device.write_offsets(Sample { x: 1, y: 2, z: 3 }).unwrap();
+    device
+        .data_format()
+        .write(|w| w.set_full_res(true).set_range(reg::GRange::Sixteen))
+        .unwrap();
+
+    device.destroy().done();
+}
@@ -1,9 +1,36 @@
 use adxl345_hal::data_bus::i2c as adxl_i2c;
+use adxl345_hal::data_bus::I2CBus;
 use adxl345_hal::register as reg;
 use adxl345_hal::register::Register;
-use adxl345_hal::ADXL345;
+use adxl345_hal::{
+    ActivityConfig, ActivityEvent, Adxl345Config, Axes, Calibration, Coupling, Error, Events,
+    FreeFallConfig, FreeFallEvent, InactivityConfig, IntPin, Interrupt, Measuring, Orientation,
+    PowerState, RawSample, Sample, SixPositionCalibration, TapConfig, TapEvent, ADXL345, DEVICE_ID,
+};
 
+use embedded_hal_mock::delay::MockNoop;
 use embedded_hal_mock::i2c;
+use embedded_hal_mock::pin;
+use embedded_hal_mock::MockError;
+use std::io::ErrorKind;
+
+/// Driver at `address` brought into measuring mode, then expecting `expect`
+fn measuring(
+    address: adxl_i2c::Address,
+    expect: &[i2c::Transaction],
+) -> ADXL345<I2CBus<i2c::Mock>, Measuring> {
+    let mut startup = vec![
+        i2c::Transaction::write_read(address as u8, vec![reg::DATA_FORMAT::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(address as u8, vec![reg::POWER_CTL::ADDRESS], vec![0]),
+        i2c::Transaction::write(address as u8, vec![reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+    ];
+    startup.extend_from_slice(expect);
+
+    ADXL345::from_i2c(i2c::Mock::new(&startup), address)
+        .into_measuring()
+        .map_err(|(_, e)| e)
+        .unwrap()
+}
 
 #[test]
 fn read_devid() {
@@ -23,3 +50,838 @@ fn read_devid() {
 
     device.destroy().done();
 }
+
+#[test]
+fn probe() {
+    const DEFAULT: u8 = adxl_i2c::Address::Default as u8;
+    const ALT: u8 = adxl_i2c::Address::Alt as u8;
+    let expect = vec![
+        // probe_i2c, wrong device
+        i2c::Transaction::write_read(DEFAULT, vec![reg::DEVID::ADDRESS], vec![0x00]),
+        // probe_i2c_any, nothing at the default address
+        i2c::Transaction::write_read(DEFAULT, vec![reg::DEVID::ADDRESS], vec![0])
+            .with_error(MockError::Io(ErrorKind::Other)),
+        i2c::Transaction::write_read(ALT, vec![reg::DEVID::ADDRESS], vec![DEVICE_ID]),
+        // probe_i2c_any, wrong device at the default address and nothing at
+        // the other
+        i2c::Transaction::write_read(DEFAULT, vec![reg::DEVID::ADDRESS], vec![0x1D]),
+        i2c::Transaction::write_read(ALT, vec![reg::DEVID::ADDRESS], vec![0])
+            .with_error(MockError::Io(ErrorKind::Other)),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let (mock, error) = match ADXL345::probe_i2c(mock, adxl_i2c::Address::Default) {
+        Err(failure) => failure,
+        Ok(_) => panic!("wrong device ID accepted"),
+    };
+    assert!(matches!(error, Error::InvalidDeviceId(0x00)));
+
+    let (device, address) = ADXL345::probe_i2c_any(mock).map_err(|(_, e)| e).unwrap();
+    assert_eq!(address, adxl_i2c::Address::Alt);
+
+    let (mut mock, error) = match ADXL345::probe_i2c_any(device.destroy()) {
+        Err(failure) => failure,
+        Ok(_) => panic!("wrong device ID accepted"),
+    };
+    assert!(matches!(error, Error::InvalidDeviceId(0x1D)));
+
+    mock.done();
+}
+
+#[test]
+fn read_raw() {
+    const ADDRESS: u8 = adxl_i2c::Address::Alt as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x10, 0x00, 0xF0, 0xFF, 0x00, 0x01],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1000]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0]),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Alt, &expect);
+
+    assert_eq!(
+        device.read_raw().unwrap(),
+        RawSample {
+            x: 16,
+            y: -16,
+            z: 256
+        }
+    );
+
+    let device = device.into_standby().map_err(|(_, e)| e).unwrap();
+    device.destroy().done();
+}
+
+#[test]
+fn bus_error_context() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect =
+        vec![
+            i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![0; 6])
+                .with_error(MockError::Io(ErrorKind::Other)),
+        ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    let error = match device.read_raw() {
+        Err(nb::Error::Other(error)) => error,
+        other => panic!("unexpected {other:?}"),
+    };
+    assert_eq!(error.register(), Some(reg::DATAX0::ADDRESS));
+    assert!(matches!(error, Error::Bus { register: 0x32, .. }));
+    assert!(error.to_string().starts_with("bus error at register 0x32:"));
+
+    device.destroy().done();
+}
+
+#[test]
+fn read_mg_tracks_data_format() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0000_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0000_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x80, 0x00, 0x80, 0xFF, 0x00, 0x00],
+        ),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device
+        .data_format()
+        .write(|w| w.set_range(reg::GRange::Four))
+        .unwrap();
+    let mut device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+
+    assert_eq!(
+        device.read_mg().unwrap(),
+        Sample {
+            x: 1000,
+            y: -1000,
+            z: 0
+        }
+    );
+
+    device.destroy().done();
+}
+
+#[test]
+fn read_mg_follows_device_range() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        // Left at ±4 g by an earlier run of the host
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0000_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x80, 0x00, 0x80, 0xFF, 0x00, 0x00],
+        ),
+    ];
+
+    let device = ADXL345::from_i2c(i2c::Mock::new(&expect), adxl_i2c::Address::Default);
+    let mut device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+
+    assert_eq!(
+        device.read_mg().unwrap(),
+        Sample {
+            x: 1000,
+            y: -1000,
+            z: 0
+        }
+    );
+
+    device.destroy().done();
+}
+
+#[test]
+fn apply_config() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let config = Adxl345Config {
+        range: reg::GRange::Sixteen,
+        full_resolution: true,
+        data_rate: reg::OutputDataRateHz::_400,
+        fifo_mode: reg::FIFOMode::Stream,
+        fifo_samples: 16,
+        interrupts: Interrupt::WATERMARK | Interrupt::SINGLE_TAP,
+        int2: Interrupt::WATERMARK,
+        tap_threshold: 48,
+        tap_duration: 16,
+        tap_axes: Axes::Z,
+        activity_axes: Axes::all(),
+        activity_ac: true,
+        ..Default::default()
+    };
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::INT_ENABLE::ADDRESS, 0]),
+        i2c::Transaction::write(
+            ADDRESS,
+            vec![
+                reg::THRESH_TAP::ADDRESS,
+                48,
+                0,
+                0,
+                0,
+                16,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0b1111_0000,
+                0,
+                0,
+            ],
+        ),
+        i2c::Transaction::write(ADDRESS, vec![reg::TAP_AXES::ADDRESS, 0b0000_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::BW_RATE::ADDRESS, 0b0000_1100]),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0000_1011]),
+        i2c::Transaction::write(ADDRESS, vec![reg::FIFO_CTL::ADDRESS, 0b1001_0000]),
+        i2c::Transaction::write(ADDRESS, vec![reg::INT_MAP::ADDRESS, 0b0000_0010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::INT_ENABLE::ADDRESS, 0b0100_0010]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device.apply_config(&config).unwrap();
+    assert_eq!(device.scale().lsb_per_g(), 256);
+
+    device.destroy().done();
+}
+
+#[test]
+fn read_config() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    // THRESH_TAP to INT_MAP
+    let mut registers = vec![0u8; 19];
+    registers[0x1D - 0x1D] = 48; // THRESH_TAP
+    registers[0x1E - 0x1D] = (-3i8) as u8; // OFSX
+    registers[0x27 - 0x1D] = 0b0111_1000; // ACT_INACT_CTL
+    registers[0x2A - 0x1D] = 0b0000_1001; // TAP_AXES
+    registers[0x2C - 0x1D] = 0b0001_1001; // BW_RATE
+    registers[0x2E - 0x1D] = 0b0100_0000; // INT_ENABLE
+    let expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::THRESH_TAP::ADDRESS], registers),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0010_1010]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_CTL::ADDRESS], vec![0b1110_0101]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    assert_eq!(
+        device.read_config().unwrap(),
+        Adxl345Config {
+            range: reg::GRange::Eight,
+            full_resolution: true,
+            int_invert: true,
+            data_rate: reg::OutputDataRateHz::_50,
+            low_power: true,
+            fifo_mode: reg::FIFOMode::Trigger,
+            fifo_trigger: IntPin::Int2,
+            fifo_samples: 5,
+            interrupts: Interrupt::SINGLE_TAP,
+            offset: Sample { x: -3, y: 0, z: 0 },
+            tap_threshold: 48,
+            tap_axes: Axes::Z,
+            tap_suppress: true,
+            activity_axes: Axes::all(),
+            inactivity_ac: true,
+            ..Default::default()
+        }
+    );
+    assert_eq!(device.scale().lsb_per_g(), 256);
+
+    device.destroy().done();
+}
+
+#[test]
+fn configure_tap() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let mut tap = TapConfig {
+        threshold_mg: 3000,
+        duration_us: 10_000,
+        latency_ms: 100,
+        window_ms: 300,
+        axes: Axes::X | Axes::Z,
+        suppress: false,
+    };
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::THRESH_TAP::ADDRESS, 48]),
+        i2c::Transaction::write(ADDRESS, vec![reg::DUR::ADDRESS, 16, 80, 240]),
+        i2c::Transaction::write(ADDRESS, vec![reg::TAP_AXES::ADDRESS, 0b0000_0101]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_TAP_STATUS::ADDRESS],
+            vec![0b0000_0100],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b0110_0000]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device.configure_tap(&tap).unwrap();
+
+    tap.window_ms = 320;
+    assert!(matches!(
+        device.configure_tap(&tap),
+        Err(nb::Error::Other(Error::OutOfRange {
+            field: "window_ms",
+            value: 320
+        }))
+    ));
+
+    let status = device.act_tap_status().read().unwrap();
+    let source = device.int_source().read().unwrap();
+    assert_eq!(
+        TapEvent::decode(&source, &status),
+        Some(TapEvent::Double { axis: Axes::X })
+    );
+
+    device.destroy().done();
+}
+
+#[test]
+fn configure_activity_inactivity() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::THRESH_ACT::ADDRESS, 4]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_INACT_CTL::ADDRESS],
+            vec![0b0000_0111],
+        ),
+        i2c::Transaction::write(ADDRESS, vec![reg::ACT_INACT_CTL::ADDRESS, 0b1111_0111]),
+        i2c::Transaction::write(ADDRESS, vec![reg::THRESH_INACT::ADDRESS, 3]),
+        i2c::Transaction::write(ADDRESS, vec![reg::TIME_INACT::ADDRESS, 5]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_INACT_CTL::ADDRESS],
+            vec![0b1111_0111],
+        ),
+        i2c::Transaction::write(ADDRESS, vec![reg::ACT_INACT_CTL::ADDRESS, 0b1111_0001]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_TAP_STATUS::ADDRESS],
+            vec![0b0010_0000],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b0001_0000]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device
+        .configure_activity(&ActivityConfig {
+            threshold_mg: 250,
+            axes: Axes::all(),
+            coupling: Coupling::Ac,
+        })
+        .unwrap();
+    device
+        .configure_inactivity(&InactivityConfig {
+            threshold_mg: 187,
+            time_s: 5,
+            axes: Axes::Z,
+            coupling: Coupling::Dc,
+        })
+        .unwrap();
+
+    let status = device.act_tap_status().read().unwrap();
+    let source = device.int_source().read().unwrap();
+    assert_eq!(
+        ActivityEvent::decode(&source, &status),
+        Some(ActivityEvent { axis: Axes::Y })
+    );
+    assert_eq!(TapEvent::decode(&source, &status), None);
+
+    device.destroy().done();
+}
+
+#[test]
+fn configure_free_fall() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::THRESH_FF::ADDRESS, 7]),
+        i2c::Transaction::write(ADDRESS, vec![reg::TIME_FF::ADDRESS, 40]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b1000_0100]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device
+        .configure_free_fall(&FreeFallConfig {
+            threshold_mg: 437,
+            time_ms: 200,
+        })
+        .unwrap();
+    assert!(matches!(
+        device.configure_free_fall(&FreeFallConfig {
+            threshold_mg: 200,
+            time_ms: 200,
+        }),
+        Err(nb::Error::Other(Error::OutOfRange {
+            field: "threshold_mg",
+            value: 200
+        }))
+    ));
+
+    let source = device.int_source().read().unwrap();
+    assert_eq!(FreeFallEvent::decode(&source), Some(FreeFallEvent));
+
+    device.destroy().done();
+}
+
+#[test]
+fn read_fifo() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_CTL::ADDRESS], vec![0b0010_0000]),
+        i2c::Transaction::write(ADDRESS, vec![reg::FIFO_CTL::ADDRESS, 0b1011_0000]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_STATUS::ADDRESS], vec![3]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![1, 0, 2, 0, 3, 0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![4, 0, 5, 0, 6, 0]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device.configure_fifo(reg::FIFOMode::Stream, 16).unwrap();
+    assert!(matches!(
+        device.configure_fifo(reg::FIFOMode::Fifo, 32),
+        Err(nb::Error::Other(Error::OutOfRange {
+            field: "watermark",
+            value: 32
+        }))
+    ));
+    let mut device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+
+    let mut samples = [RawSample::default(); 2];
+    assert_eq!(
+        device
+            .read_fifo(&mut samples, &mut MockNoop::new())
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        samples,
+        [
+            RawSample { x: 1, y: 2, z: 3 },
+            RawSample { x: 4, y: 5, z: 6 }
+        ]
+    );
+
+    device.destroy().done();
+}
+
+#[test]
+fn fifo_overrun() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        // poll_events, with a single tap still latched
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_TAP_STATUS::ADDRESS],
+            vec![0b0000_0001],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b0100_0011]),
+        // read_fifo leaves INT_SOURCE alone
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_STATUS::ADDRESS], vec![32]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![1, 0, 2, 0, 3, 0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![4, 0, 5, 0, 6, 0]),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    let events = device.poll_events().unwrap();
+    assert!(events.interrupts.contains(Interrupt::SINGLE_TAP));
+    assert!(matches!(
+        events.check_overrun::<()>(),
+        Err(Error::FifoOverrun)
+    ));
+
+    let mut samples = [RawSample::default(); 2];
+    assert_eq!(
+        device
+            .read_fifo(&mut samples, &mut MockNoop::new())
+            .unwrap(),
+        2
+    );
+    assert_eq!(samples[1], RawSample { x: 4, y: 5, z: 6 });
+
+    device.destroy().done();
+}
+
+#[test]
+fn trigger_capture() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let mut expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_CTL::ADDRESS], vec![0b1100_1010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::FIFO_CTL::ADDRESS, 0b0000_1010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::FIFO_CTL::ADDRESS, 0b1110_1010]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_STATUS::ADDRESS], vec![20]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_STATUS::ADDRESS], vec![0x80 | 32]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::FIFO_CTL::ADDRESS], vec![0b1110_1010]),
+    ];
+    expect.extend((0..32).map(|i| {
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![i, 0, 0, 0, 0, 0])
+    }));
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device.arm_trigger(IntPin::Int2, 10).unwrap();
+    let mut device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+
+    let mut delay = MockNoop::new();
+    assert!(matches!(
+        device.capture_trigger(&mut delay),
+        Err(nb::Error::WouldBlock)
+    ));
+    let capture = device.capture_trigger(&mut delay).unwrap();
+    assert_eq!(capture.samples().len(), 32);
+    assert_eq!(capture.pre_trigger().len(), 10);
+    assert_eq!(capture.post_trigger()[0], RawSample { x: 10, y: 0, z: 0 });
+
+    device.destroy().done();
+}
+
+#[test]
+fn interrupts() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::INT_ENABLE::ADDRESS, 0b0110_0100]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_MAP::ADDRESS], vec![0b1000_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::INT_MAP::ADDRESS, 0b1110_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_MAP::ADDRESS], vec![0b1110_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::INT_MAP::ADDRESS, 0b0110_0001]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_TAP_STATUS::ADDRESS],
+            vec![0b0000_1001],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b1100_0100]),
+    ];
+
+    let mock = i2c::Mock::new(&expect);
+
+    let mut device = ADXL345::from_i2c(mock, adxl_i2c::Address::Default);
+
+    device
+        .enable_interrupts(Interrupt::SINGLE_TAP | Interrupt::DOUBLE_TAP | Interrupt::FREE_FALL)
+        .unwrap();
+    device
+        .route_interrupts(Interrupt::SINGLE_TAP | Interrupt::DOUBLE_TAP, IntPin::Int2)
+        .unwrap();
+    device
+        .route_interrupts(Interrupt::DATA_READY, IntPin::Int1)
+        .unwrap();
+
+    assert_eq!(
+        device.poll_events().unwrap(),
+        Events {
+            interrupts: Interrupt::DATA_READY | Interrupt::SINGLE_TAP | Interrupt::FREE_FALL,
+            tap: Some(TapEvent::Single { axis: Axes::Z }),
+            activity: None,
+            free_fall: Some(FreeFallEvent),
+            asleep: true,
+        }
+    );
+
+    device.destroy().done();
+}
+
+#[test]
+fn wait_for_data_ready() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b0000_0010]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b1000_0010]),
+        // Active-low interrupts, set up in standby
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1000]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0]),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0010_0000]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0010_0000]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::ACT_TAP_STATUS::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::INT_SOURCE::ADDRESS], vec![0b1000_0000]),
+    ];
+    let pin_expect = vec![
+        pin::Transaction::get(pin::State::High),
+        pin::Transaction::get(pin::State::Low),
+        pin::Transaction::get(pin::State::Low),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    assert!(matches!(
+        device.wait_for_data_ready(),
+        Err(nb::Error::WouldBlock)
+    ));
+    device.wait_for_data_ready().unwrap();
+
+    let mut device = device.into_standby().map_err(|(_, e)| e).unwrap();
+    device
+        .data_format()
+        .write(|w| w.set_int_invert(true))
+        .unwrap();
+    let device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+    let mut device = device.with_int_pin(pin::Mock::new(&pin_expect));
+
+    assert!(matches!(
+        device.wait_for_data_ready(),
+        Err(nb::Error::WouldBlock)
+    ));
+    device.wait_for_data_ready().unwrap();
+    assert_eq!(
+        device.wait_for_event().unwrap().interrupts,
+        Interrupt::DATA_READY
+    );
+
+    let (device, mut pin) = device.release_int_pin();
+    pin.done();
+    device.destroy().done();
+}
+
+#[test]
+fn power_modes() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        // enter_sleep
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1000]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1110]),
+        // power_state
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1110]),
+        // into_standby
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0110]),
+        // power_state
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_0110]),
+        // into_measuring, waking up
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_0110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1010]),
+        // enable_auto_sleep
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_INACT_CTL::ADDRESS],
+            vec![0b0111_0000],
+        ),
+        // power_state
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0011_1011]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::ACT_TAP_STATUS::ADDRESS], vec![0]),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    device.enter_sleep(reg::ReadingFrequencyHz::Two).unwrap();
+    assert_eq!(
+        device.power_state().unwrap(),
+        PowerState::Sleeping(reg::ReadingFrequencyHz::Two)
+    );
+
+    let mut device = device.into_standby().map_err(|(_, e)| e).unwrap();
+    assert_eq!(device.power_state().unwrap(), PowerState::Standby);
+
+    let mut device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+    assert!(matches!(
+        device.enable_auto_sleep(reg::ReadingFrequencyHz::One),
+        Err(nb::Error::Other(Error::NotConfigured("inactivity")))
+    ));
+    assert_eq!(device.power_state().unwrap(), PowerState::Measuring);
+
+    device.destroy().done();
+}
+
+#[test]
+fn power_sequences() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        // wake: measuring, asleep at 4 Hz
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1101]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0101]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1001]),
+        // enable_auto_sleep, with activity and inactivity on X
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::ACT_INACT_CTL::ADDRESS],
+            vec![0b0100_0100],
+        ),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0000_1001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0011_0010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0011_1010]),
+        // disable_auto_sleep, while auto-sleeping
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0011_1110]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::POWER_CTL::ADDRESS], vec![0b0011_1110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0011_0110]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_0010]),
+        i2c::Transaction::write(ADDRESS, vec![reg::POWER_CTL::ADDRESS, 0b0000_1010]),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    device.wake().unwrap();
+    device
+        .enable_auto_sleep(reg::ReadingFrequencyHz::Two)
+        .unwrap();
+    device.disable_auto_sleep().unwrap();
+
+    device.destroy().done();
+}
+
+#[test]
+fn self_test() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::BW_RATE::ADDRESS], vec![0b0000_1010]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS], vec![0b0010_0001]),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0010_1011]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![0, 0, 0, 0, 0, 1]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::DATAX0::ADDRESS], vec![0, 0, 0, 0, 0, 1]),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b1010_1011]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x80, 0x00, 0x80, 0xFF, 0x00, 0x02],
+        ),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x80, 0x00, 0x80, 0xFF, 0x00, 0x02],
+        ),
+        i2c::Transaction::write(ADDRESS, vec![reg::DATA_FORMAT::ADDRESS, 0b0010_0001]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::BW_RATE::ADDRESS], vec![0b0001_1010]),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    let mut delay = MockNoop::new();
+    let report = device.self_test(2500, 2, &mut delay).unwrap();
+    assert_eq!(
+        report.off,
+        Sample {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0
+        }
+    );
+    assert_eq!(
+        report.change(),
+        Sample {
+            x: 0.5,
+            y: -0.5,
+            z: 1.0
+        }
+    );
+    assert!(report.passed());
+    assert!(report.check::<()>().is_ok());
+    assert_eq!(device.scale().lsb_per_g(), 128);
+
+    assert!(matches!(
+        device.self_test(2500, 2, &mut delay),
+        Err(nb::Error::Other(Error::OutOfRange {
+            field: "data_rate",
+            value: 0b1010
+        }))
+    ));
+
+    device.destroy().done();
+}
+
+#[test]
+fn calibrate_offsets() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write(ADDRESS, vec![reg::OFSX::ADDRESS, 0, 0, 0]),
+        i2c::Transaction::write_read(ADDRESS, vec![reg::BW_RATE::ADDRESS], vec![0b0000_1010]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![6, 0, 0xF6, 0xFF, 0xF0, 0],
+        ),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![10, 0, 0xFA, 0xFF, 0xF0, 0],
+        ),
+        i2c::Transaction::write(ADDRESS, vec![reg::OFSX::ADDRESS, 0xFE, 2, 4]),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    assert_eq!(
+        device
+            .calibrate_offsets(Orientation::ZUp, 2, &mut MockNoop::new())
+            .unwrap(),
+        Sample { x: -2, y: 2, z: 4 }
+    );
+
+    device.destroy().done();
+}
+
+#[test]
+fn six_position_calibration() {
+    const ADDRESS: u8 = adxl_i2c::Address::Default as u8;
+    let expect = vec![
+        i2c::Transaction::write_read(ADDRESS, vec![reg::BW_RATE::ADDRESS], vec![0b0000_1010]),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x20, 0x01, 0, 0, 0, 0],
+        ),
+        i2c::Transaction::write_read(
+            ADDRESS,
+            vec![reg::DATAX0::ADDRESS],
+            vec![0x20, 0x01, 0x40, 0, 0, 0],
+        ),
+    ];
+
+    let mut device = measuring(adxl_i2c::Address::Default, &expect);
+
+    let mut positions = SixPositionCalibration::new();
+    device
+        .record_position(&mut positions, Orientation::XUp, 1, &mut MockNoop::new())
+        .unwrap();
+    assert_eq!(positions.missing(), Some(Orientation::XDown));
+
+    let mut calibration = Calibration::default();
+    calibration.gain.x = 0.8;
+    calibration.bias.y = 0.25;
+    device.set_calibration(Some(calibration));
+    assert_eq!(device.read_mg().unwrap(), Sample { x: 900, y: 0, z: 0 });
+
+    device.destroy().done();
+}
@@ -1,33 +1,433 @@
-// TODO: Cannot test until https://github.com/dbrgn/embedded-hal-mock/issues/25 gets fixed
-// use adxl345_hal::data_bus::SPIBus;
-// use adxl345_hal::register as reg;
-// use adxl345_hal::register::Register;
-// use adxl345_hal::ADXL345;
+mod word_u16 {
+    use std::collections::VecDeque;
 
-// use embedded_hal_mock::pin::{Mock as PinMock, State as PinState, Transaction as PinTransaction};
-// use embedded_hal_mock::spi::{Mock as SpiMock, Transaction as SpiTransaction, self};
+    use adxl345_hal::data_bus::{DataBus, SPIBus, SpiError};
+    use adxl345_hal::register as reg;
+    use adxl345_hal::register::Register;
+    use adxl345_hal::{Error, RawSample, ADXL345, DEVICE_ID};
 
-// #[test]
-// fn read_devid() {
-//     const DEVICE_ID: u8 = 0b1110_0101;
-//     let pin_expect = vec![
-//         PinTransaction::get(PinState::High),
-//         PinTransaction::set(PinState::Low),
-//         PinTransaction::set(PinState::High)
-//     ];
+    use embedded_hal::spi::FullDuplex;
+    use embedded_hal_mock::pin::{
+        Mock as PinMock, State as PinState, Transaction as PinTransaction,
+    };
 
-//     let pin_mock = PinMock::new(&pin_expect);
+    /// 16-bit peripheral that is busy on every other call
+    struct Busy {
+        expect: VecDeque<(u16, u16)>,
+        reply: Option<u16>,
+        ready: bool,
+    }
 
-//     let spi_expect = vec![
-//         SpiTransaction::send(u16::from_be_bytes([reg::DEVID, 0])),
-//         SpiTransaction::read(u16::from_be_bytes([0, DEVIDE_ID]))
-//     ];
+    impl Busy {
+        fn new(expect: &[(u16, u16)]) -> Self {
+            Self {
+                expect: expect.iter().copied().collect(),
+                reply: None,
+                ready: false,
+            }
+        }
 
-//     let mock = spi::Mock::new(&spi_expect);
+        fn poll(&mut self) -> nb::Result<(), ()> {
+            self.ready = !self.ready;
+            if self.ready {
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
 
-//     let mut device = ADXL345::from_spi_cs(mock, pin_mock);
+    impl FullDuplex<u16> for Busy {
+        type Error = ();
 
-//     assert_eq!(device.devid().read().unwrap().value(), DEVICE_ID);
+        fn read(&mut self) -> nb::Result<u16, Self::Error> {
+            self.poll()?;
+            Ok(self.reply.take().unwrap())
+        }
 
-//     device.destroy().done();
-// }
+        fn send(&mut self, word: u16) -> nb::Result<(), Self::Error> {
+            self.poll()?;
+            let (sent, reply) = self.expect.pop_front().unwrap();
+            assert_eq!(word, sent);
+            self.reply = Some(reply);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn probe() {
+        let pin_expect = [
+            PinTransaction::set(PinState::Low),
+            PinTransaction::set(PinState::High),
+        ];
+        let devid = u16::from_be_bytes([0x80 | reg::DEVID::ADDRESS, 0]);
+
+        let device = ADXL345::probe_spi(
+            Busy::new(&[(devid, u16::from(DEVICE_ID))]),
+            PinMock::new(&pin_expect),
+        )
+        .map_err(|(_, e)| e)
+        .unwrap();
+        let (spi, mut cs) = device.destroy();
+        assert!(spi.expect.is_empty());
+        cs.done();
+
+        let ((spi, mut cs), error) =
+            match ADXL345::probe_spi(Busy::new(&[(devid, 0x00FF)]), PinMock::new(&pin_expect)) {
+                Err(failure) => failure,
+                Ok(_) => panic!("wrong device ID accepted"),
+            };
+        assert!(matches!(error, Error::InvalidDeviceId(0xFF)));
+        assert!(spi.expect.is_empty());
+        cs.done();
+    }
+
+    #[test]
+    fn resumable_reads() {
+        let pin_expect: Vec<_> = (0..4)
+            .flat_map(|_| {
+                [
+                    PinTransaction::set(PinState::Low),
+                    PinTransaction::set(PinState::High),
+                ]
+            })
+            .collect();
+        let spi_expect = [
+            // into_measuring
+            (u16::from_be_bytes([0x80 | reg::DATA_FORMAT::ADDRESS, 0]), 0),
+            (u16::from_be_bytes([0x80 | reg::POWER_CTL::ADDRESS, 0]), 0),
+            (
+                u16::from_be_bytes([reg::POWER_CTL::ADDRESS, 0b0000_1000]),
+                0,
+            ),
+            // read_raw, padded to whole words
+            (u16::from_be_bytes([0xC0 | reg::DATAX0::ADDRESS, 0]), 0x0010),
+            (0, 0x00F0),
+            (0, 0xFF00),
+            (0, 0x0100),
+        ];
+
+        let device = ADXL345::from_spi_cs(Busy::new(&spi_expect), PinMock::new(&pin_expect));
+        let mut device = device.into_measuring().map_err(|(_, e)| e).unwrap();
+
+        let mut polls = 0;
+        let sample = loop {
+            polls += 1;
+            match device.read_raw() {
+                Ok(sample) => break sample,
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(error)) => panic!("{error:?}"),
+            }
+        };
+        assert_eq!(
+            sample,
+            RawSample {
+                x: 16,
+                y: -16,
+                z: 256
+            }
+        );
+        assert_eq!(polls, 9);
+
+        let (spi, mut cs) = device.destroy();
+        assert!(spi.expect.is_empty());
+        cs.done();
+    }
+
+    #[test]
+    fn changed_arguments_restart() {
+        let pin_expect = [
+            PinTransaction::set(PinState::Low),
+            PinTransaction::set(PinState::High),
+            PinTransaction::set(PinState::Low),
+            PinTransaction::set(PinState::High),
+        ];
+        let spi_expect = [
+            (u16::from_be_bytes([reg::OFSX::ADDRESS, 1]), 0),
+            (u16::from_be_bytes([reg::OFSX::ADDRESS, 2]), 0),
+        ];
+
+        let mut bus = SPIBus::<_, _, u16>::new(Busy::new(&spi_expect), PinMock::new(&pin_expect));
+
+        // Sent, but the reply isn't there yet
+        assert!(matches!(
+            bus.write::<reg::OFSX>(1),
+            Err(nb::Error::WouldBlock)
+        ));
+        // Another value starts a transaction of its own
+        assert!(matches!(
+            bus.write::<reg::OFSX>(2),
+            Err(nb::Error::WouldBlock)
+        ));
+        bus.write::<reg::OFSX>(2).unwrap();
+
+        let (spi, mut cs) = bus.destroy();
+        assert!(spi.expect.is_empty());
+        cs.done();
+    }
+
+    #[test]
+    fn padding_reads_rejected() {
+        let mut bus = SPIBus::<_, _, u16>::new(Busy::new(&[]), PinMock::new(&[]));
+
+        // Padded up to INT_SOURCE
+        assert!(matches!(
+            bus.read_all::<reg::INT_ENABLE>(&mut [0; 2]),
+            Err(nb::Error::Other(SpiError::InvalidReadBuffer { len: 2 }))
+        ));
+        // Padded up to DATAX0
+        assert!(matches!(
+            bus.read_all::<reg::BW_RATE>(&mut [0; 6]),
+            Err(nb::Error::Other(SpiError::InvalidReadBuffer { len: 6 }))
+        ));
+
+        let (_, mut cs) = bus.destroy();
+        cs.done();
+    }
+}
+
+mod word_u8 {
+    use adxl345_hal::data_bus::{DataBus, SPIBus};
+    use adxl345_hal::register as reg;
+    use adxl345_hal::register::Register;
+
+    use embedded_hal_mock::pin::{
+        Mock as PinMock, State as PinState, Transaction as PinTransaction,
+    };
+    use embedded_hal_mock::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    /// Exchange of `byte` as a single 8-bit word, answered by `reply`
+    fn word(byte: u8, reply: u8) -> [SpiTransaction; 2] {
+        [SpiTransaction::send(byte), SpiTransaction::read(reply)]
+    }
+
+    #[test]
+    fn odd_bursts_unpadded() {
+        let pin_expect: Vec<_> = (0..2)
+            .flat_map(|_| {
+                [
+                    PinTransaction::set(PinState::Low),
+                    PinTransaction::set(PinState::High),
+                ]
+            })
+            .collect();
+        let mut spi_expect = Vec::new();
+        // 7 words for the data registers, which 16-bit words would pad to 8
+        spi_expect.extend(word(0xC0 | reg::DATAX0::ADDRESS, 0));
+        for byte in [0x10, 0x00, 0xF0, 0xFF, 0x00, 0x01] {
+            spi_expect.extend(word(0, byte));
+        }
+        spi_expect.extend(word(0x40 | reg::OFSX::ADDRESS, 0));
+        spi_expect.extend(word(1, 0));
+        spi_expect.extend(word(2, 0));
+
+        let mut bus: SPIBus<_, _, u8> =
+            SPIBus::new(SpiMock::new(&spi_expect), PinMock::new(&pin_expect));
+
+        let mut data = [0; 6];
+        nb::block!(bus.read_all::<reg::DATAX0>(&mut data)).unwrap();
+        assert_eq!(data, [0x10, 0x00, 0xF0, 0xFF, 0x00, 0x01]);
+        nb::block!(bus.write_all::<reg::OFSX>(&[1, 2])).unwrap();
+
+        let (mut spi, mut cs) = bus.destroy();
+        spi.done();
+        cs.done();
+    }
+}
+
+mod three_wire {
+    use adxl345_hal::data_bus::HalfDuplex;
+    use adxl345_hal::register as reg;
+    use adxl345_hal::register::Register;
+    use adxl345_hal::{Adxl345Config, ADXL345};
+
+    use embedded_hal::blocking::spi::{Transfer, Write};
+    use embedded_hal_mock::pin::{
+        Mock as PinMock, State as PinState, Transaction as PinTransaction,
+    };
+    use embedded_hal_mock::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+    use embedded_hal_mock::MockError;
+
+    /// Half-duplex view of the mock, reading by clocking out zeros
+    struct Bidirectional(SpiMock);
+
+    impl HalfDuplex for Bidirectional {
+        type Error = MockError;
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.0.write(words)
+        }
+
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            words.fill(0);
+            self.0.transfer(words).map(|_| ())
+        }
+    }
+
+    #[test]
+    fn switch_over() {
+        const DEVICE_ID: u8 = 0b1110_0101;
+        let pin_expect: Vec<_> = (0..3)
+            .flat_map(|_| {
+                [
+                    PinTransaction::set(PinState::Low),
+                    PinTransaction::set(PinState::High),
+                ]
+            })
+            .collect();
+        let spi_expect = vec![
+            // DATA_FORMAT modify over 4 wires
+            SpiTransaction::send(0x80 | reg::DATA_FORMAT::ADDRESS),
+            SpiTransaction::read(0),
+            SpiTransaction::send(0),
+            SpiTransaction::read(0b0000_1011),
+            SpiTransaction::send(reg::DATA_FORMAT::ADDRESS),
+            SpiTransaction::read(0),
+            SpiTransaction::send(0b0100_1011),
+            SpiTransaction::read(0),
+            // DEVID read over 3 wires
+            SpiTransaction::write(vec![0x80 | reg::DEVID::ADDRESS]),
+            SpiTransaction::transfer(vec![0], vec![DEVICE_ID]),
+        ];
+
+        let device = ADXL345::from_spi_cs_u8(SpiMock::new(&spi_expect), PinMock::new(&pin_expect));
+        let mut device = device
+            .into_three_wire(Bidirectional)
+            .map_err(|(_, e)| e)
+            .unwrap();
+
+        assert_eq!(device.devid().read().unwrap().value(), DEVICE_ID);
+        assert_eq!(device.scale().lsb_per_g(), 256);
+
+        let (mut spi, mut cs) = device.destroy();
+        spi.0.done();
+        cs.done();
+    }
+
+    #[test]
+    fn attach_keeps_three_wire() {
+        let pin_expect: Vec<_> = (0..8)
+            .flat_map(|_| {
+                [
+                    PinTransaction::set(PinState::Low),
+                    PinTransaction::set(PinState::High),
+                ]
+            })
+            .collect();
+        let mut spi_expect = vec![
+            SpiTransaction::write(vec![reg::INT_ENABLE::ADDRESS, 0]),
+            SpiTransaction::write(vec![0x40 | reg::THRESH_TAP::ADDRESS]),
+            SpiTransaction::write(vec![0; 13]),
+        ];
+        spi_expect.extend(
+            [
+                (reg::TAP_AXES::ADDRESS, 0),
+                (reg::BW_RATE::ADDRESS, 0b0000_1010),
+                // SPI bit kept set
+                (reg::DATA_FORMAT::ADDRESS, 0b0100_0000),
+                (reg::FIFO_CTL::ADDRESS, 0),
+                (reg::INT_MAP::ADDRESS, 0),
+                (reg::INT_ENABLE::ADDRESS, 0),
+            ]
+            .map(|(address, value)| SpiTransaction::write(vec![address, value])),
+        );
+
+        let mut device = ADXL345::from_three_wire(
+            Bidirectional(SpiMock::new(&spi_expect)),
+            PinMock::new(&pin_expect),
+        );
+        device.apply_config(&Adxl345Config::default()).unwrap();
+
+        let (mut spi, mut cs) = device.destroy();
+        spi.0.done();
+        cs.done();
+    }
+}
+
+mod blocking {
+    use adxl345_hal::data_bus::{BlockingSPIBus, BlockingSpiError, DataBus};
+    use adxl345_hal::register as reg;
+    use adxl345_hal::register::Register;
+    use adxl345_hal::{Error, ADXL345};
+
+    use embedded_hal::blocking::spi::{Transfer, Write};
+    use embedded_hal_mock::pin::{
+        Mock as PinMock, State as PinState, Transaction as PinTransaction,
+    };
+    use embedded_hal_mock::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[test]
+    fn burst_capacity() {
+        let pin_expect = [
+            PinTransaction::set(PinState::Low),
+            PinTransaction::set(PinState::High),
+        ];
+        let mut reply = vec![0; 30];
+        reply[1] = 48;
+        // THRESH_TAP to FIFO_STATUS in a single transfer
+        let spi_expect = [SpiTransaction::transfer(
+            [vec![0xC0 | reg::THRESH_TAP::ADDRESS], vec![0; 29]].concat(),
+            reply,
+        )];
+
+        let mut bus = BlockingSPIBus::new(SpiMock::new(&spi_expect), PinMock::new(&pin_expect));
+
+        let mut registers = [0; 29];
+        bus.read_all::<reg::THRESH_TAP>(&mut registers).unwrap();
+        assert_eq!(registers[0], 48);
+
+        // One register more does not fit, and nothing is sent
+        assert!(matches!(
+            bus.read_all::<reg::THRESH_TAP>(&mut [0; 30]),
+            Err(nb::Error::Other(BlockingSpiError::Capacity))
+        ));
+        assert!(matches!(
+            bus.write_all::<reg::THRESH_TAP>(&[0; 30]),
+            Err(nb::Error::Other(BlockingSpiError::Capacity))
+        ));
+
+        let (mut spi, mut cs) = bus.destroy();
+        spi.done();
+        cs.done();
+    }
+
+    /// Peripheral whose transfers always fail
+    struct BrokenTransfer;
+
+    impl Transfer<u8> for BrokenTransfer {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, _words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+            Err(())
+        }
+    }
+
+    impl Write<u8> for BrokenTransfer {
+        type Error = ();
+
+        fn write(&mut self, _words: &[u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn chip_select_released_on_failure() {
+        let pin_expect = [
+            PinTransaction::set(PinState::Low),
+            PinTransaction::set(PinState::High),
+        ];
+
+        let mut device = ADXL345::from_blocking_spi_cs(BrokenTransfer, PinMock::new(&pin_expect));
+
+        assert!(matches!(
+            device.devid().read(),
+            Err(nb::Error::Other(Error::Bus {
+                source: BlockingSpiError::Transfer(()),
+                ..
+            }))
+        ));
+
+        let (_, mut cs) = device.destroy();
+        cs.done();
+    }
+}